@@ -0,0 +1,298 @@
+// Conflict-directed backjumping: every search level keeps the set of earlier levels whose
+// assignments ruled out one of its digits. Once a level runs out of digits the search jumps straight
+// back to the latest level in that set, skipping levels that had nothing to do with the conflict.
+// Optionally the conflict sets are kept as nogoods, so the same combination of assignments is
+// rejected in other branches without searching below it again.
+
+use crate::pencil::{UNITS, units_of};
+use crate::{CandidateSet, CandidateSetIterator, SolverBackend, Sudoku};
+
+// Conflict sets are bit masks of levels, this bit marks sets that came from a solution the callback
+// rejected rather than from a real conflict. Those are searched through chronologically and never
+// learned.
+const AFTER_SOLUTION: u128 = 1 << 127;
+const NO_LEVEL: u8 = u8::MAX;
+// Longer nogoods rarely match again and only slow down every assignment
+const MAX_NOGOOD_LEN: u32 = 6;
+const MAX_NOGOODS: usize = 1 << 16;
+
+pub struct Backjumping {
+    pub learn_nogoods: bool,
+}
+
+impl SolverBackend for Backjumping {
+    fn solve(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: &dyn Fn(&Sudoku) -> bool,
+        is_cancelled: &dyn Fn() -> bool,
+        check_interval: u32,
+    ) {
+        solve_backjumping(
+            sudoku,
+            allowed_candidates,
+            callback,
+            is_cancelled,
+            check_interval,
+            self.learn_nogoods,
+        );
+    }
+
+    fn is_exhaustive(&self) -> bool {
+        true
+    }
+}
+
+// A nogood is a set of (cell, digit) assignments that cannot all hold in any solution
+struct Nogoods {
+    literals: Vec<Vec<(u8, u8)>>,
+    // Nogoods containing each (cell, digit), indexed by `cell * 9 + digit - 1`
+    watches: Vec<Vec<u32>>,
+}
+
+impl Nogoods {
+    fn new() -> Self {
+        Nogoods {
+            literals: Vec::new(),
+            watches: vec![Vec::new(); 729],
+        }
+    }
+
+    fn learn(&mut self, literals: Vec<(u8, u8)>) {
+        if self.literals.len() >= MAX_NOGOODS {
+            return;
+        }
+        for (cell, digit) in &literals {
+            self.watches[*cell as usize * 9 + *digit as usize - 1].push(self.literals.len() as u32);
+        }
+        self.literals.push(literals);
+    }
+
+    // Levels of the other assignments of a nogood that placing `digit` in `cell` would complete
+    fn violated_by(
+        &self,
+        sudoku: &Sudoku,
+        level_of: &[u8; 81],
+        cell: u8,
+        digit: u8,
+    ) -> Option<u128> {
+        'nogoods: for id in &self.watches[cell as usize * 9 + digit as usize - 1] {
+            let mut levels = 0;
+            for (other, other_digit) in &self.literals[*id as usize] {
+                if *other == cell {
+                    continue;
+                }
+                if sudoku.get(*other) != *other_digit || level_of[*other as usize] == NO_LEVEL {
+                    continue 'nogoods;
+                }
+                levels |= 1 << level_of[*other as usize];
+            }
+            return Some(levels);
+        }
+        None
+    }
+}
+
+/// Fewest-candidates-first search with conflict-directed backjumping, optionally learning nogoods.
+/// Calls `is_cancelled` every `check_interval` search nodes.
+pub fn solve_backjumping(
+    sudoku: &mut Sudoku,
+    allowed_candidates: &[CandidateSet; 81],
+    callback: impl Fn(&Sudoku) -> bool,
+    is_cancelled: impl Fn() -> bool,
+    check_interval: u32,
+    learn_nogoods: bool,
+) {
+    // cells[..depth] are the cells of the current levels, cells[depth..cell_count] are still empty
+    let mut cells: [u8; 81] = [0; _];
+    let mut cell_count = 0;
+    for index in 0..81 {
+        if sudoku.is_missing(index) {
+            cells[cell_count] = index;
+            cell_count += 1;
+        }
+    }
+    let mut level_of = [NO_LEVEL; 81];
+    let mut stack: [CandidateSetIterator; 81] = [CandidateSetIterator::empty(); _];
+    let mut conflicts: [u128; 81] = [0; _];
+    let mut nogoods = Nogoods::new();
+    let mut depth = 0;
+    let mut counter = 0;
+
+    loop {
+        if depth == cell_count {
+            if callback(sudoku) || depth == 0 {
+                return;
+            }
+            //Look for the next solution by stepping back one level at a time
+            conflicts[depth - 1] |= AFTER_SOLUTION | ((1 << (depth - 1)) - 1);
+        } else {
+            let mut best = depth;
+            let mut best_candidates = CandidateSet::new();
+            for k in depth..cell_count {
+                let candidates =
+                    sudoku.get_candidates(cells[k]) & allowed_candidates[cells[k] as usize];
+                if candidates.len() < best_candidates.len() || k == depth {
+                    best = k;
+                    best_candidates = candidates;
+                    if best_candidates.len() <= 1 {
+                        break;
+                    }
+                }
+            }
+            cells.swap(depth, best);
+            stack[depth] = best_candidates.into_iter();
+            conflicts[depth] = eliminating_levels(sudoku, &level_of, cells[depth]);
+            depth += 1;
+        }
+
+        //Find the next digit to try, backjumping over levels that ran out of digits
+        loop {
+            let level = depth - 1;
+            let cell = cells[level];
+            let mut next_digit = None;
+            for digit in &mut stack[level] {
+                match nogoods.violated_by(sudoku, &level_of, cell, digit) {
+                    Some(levels) => conflicts[level] |= levels,
+                    None => {
+                        next_digit = Some(digit);
+                        break;
+                    }
+                }
+            }
+            if let Some(digit) = next_digit {
+                sudoku.set(cell, digit);
+                level_of[cell as usize] = level as u8;
+                break;
+            }
+
+            let conflict = conflicts[level];
+            if learn_nogoods
+                && conflict & AFTER_SOLUTION == 0
+                && conflict.count_ones() <= MAX_NOGOOD_LEN
+            {
+                nogoods.learn(
+                    (0..level)
+                        .filter(|l| conflict & (1 << l) != 0)
+                        .map(|l| (cells[l], sudoku.get(cells[l])))
+                        .collect(),
+                );
+            }
+            let levels = conflict & !AFTER_SOLUTION;
+            let target = if levels == 0 {
+                //Nothing assigned caused the conflict, no solutions are left
+                None
+            } else {
+                Some((127 - levels.leading_zeros()) as usize)
+            };
+            for l in target.map_or(0, |t| t + 1)..=level {
+                sudoku.set(cells[l], 0);
+                level_of[cells[l] as usize] = NO_LEVEL;
+            }
+            let Some(target) = target else {
+                return;
+            };
+            let cell = cells[target];
+            sudoku.set(cell, 0);
+            level_of[cell as usize] = NO_LEVEL;
+            conflicts[target] |= conflict & !(1 << target);
+            depth = target + 1;
+        }
+
+        counter += 1;
+        if counter >= check_interval {
+            counter = 0;
+            if is_cancelled() {
+                return;
+            }
+        }
+    }
+}
+
+// Levels of the peers that rule out a digit of `cell`. Digits ruled out by a given are excluded for
+// good and do not count.
+fn eliminating_levels(sudoku: &Sudoku, level_of: &[u8; 81], cell: u8) -> u128 {
+    let mut by_level: [u128; 9] = [0; _];
+    let mut by_given = 0_u16;
+    for unit in units_of(cell) {
+        for peer in UNITS[unit] {
+            let digit = sudoku.get(peer);
+            if digit == 0 {
+                continue;
+            }
+            match level_of[peer as usize] {
+                NO_LEVEL => by_given |= 1 << (digit - 1),
+                //Blame the earliest of the peers holding the digit, it allows the longest jump
+                level => {
+                    let bit = 1 << level;
+                    if by_level[digit as usize - 1] == 0 || bit < by_level[digit as usize - 1] {
+                        by_level[digit as usize - 1] = bit;
+                    }
+                }
+            }
+        }
+    }
+    (0..9)
+        .filter(|d| by_given & (1 << d) == 0)
+        .fold(0, |acc, d| acc | by_level[d])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ALL_CANDIDATES, DEFAULT_CHECK_INTERVAL, DynamicBacktracking, MaybeValid};
+
+    #[test]
+    fn test_solves_and_counts() {
+        for learn_nogoods in [false, true] {
+            let backend = Backjumping { learn_nogoods };
+            let mut sudoku: Sudoku =
+                "000080930379500040000073500004300070810090000700406001107609854040700000926000003"
+                    .into();
+            let solution: Sudoku =
+                "265184937379562148481973526654318279813297465792456381137629854548731692926845713"
+                    .into();
+            let did_solve = core::cell::Cell::new(false);
+            backend.solve(
+                &mut sudoku,
+                &ALL_CANDIDATES,
+                &|s| {
+                    assert!(s.is_valid());
+                    did_solve.set(*s == solution);
+                    did_solve.get()
+                },
+                &|| false,
+                DEFAULT_CHECK_INTERVAL,
+            );
+            assert!(did_solve.get());
+
+            for puzzle in [
+                "000720030000000000106008709003091000580407200000000006840650010600143900005000402",
+                "001000000005000000900000200000040000000057000000310402040500630600400805009000000",
+            ] {
+                let sudoku: Sudoku = puzzle.into();
+                assert_eq!(
+                    backend.count_solutions(&sudoku, 100),
+                    DynamicBacktracking.count_solutions(&sudoku, 100)
+                );
+            }
+
+            // The only solution has a 9 in the first cell, excluding it leaves nothing to find
+            let mut sudoku: Sudoku =
+                "000720030007006820106008709003091000580407200000000006840650010600143900005000402"
+                    .into();
+            let original = sudoku.clone();
+            let mut allowed = ALL_CANDIDATES;
+            allowed[0] &= !(1 << 8);
+            backend.solve(
+                &mut sudoku,
+                &allowed,
+                &|_| panic!(),
+                &|| false,
+                DEFAULT_CHECK_INTERVAL,
+            );
+            assert!(sudoku == original);
+        }
+    }
+}
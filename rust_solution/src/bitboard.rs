@@ -0,0 +1,193 @@
+// Alternative puzzle core: one 81 bit plane per digit instead of nibble packed cells.
+// A candidate query ORs the digit planes under the peer mask of the cell, no unit lookups needed.
+
+use crate::pencil::UNITS;
+use crate::{CandidateSet, MaybeValid, PuzzleCore, Sudoku};
+
+const fn build_peers() -> [u128; 81] {
+    let mut peers = [0; 81];
+    let mut index = 0;
+    while index < 81 {
+        let row = index / 9;
+        let col = index % 9;
+        let units = [row, 9 + col, 18 + (row / 3) * 3 + col / 3];
+        let mut u = 0;
+        while u < 3 {
+            let mut k = 0;
+            while k < 9 {
+                peers[index] |= 1 << UNITS[units[u]][k];
+                k += 1;
+            }
+            u += 1;
+        }
+        peers[index] &= !(1 << index);
+        index += 1;
+    }
+    peers
+}
+
+// Bit `i` is set for every cell sharing a row, column or subgrid with cell `index`
+const PEERS: [u128; 81] = build_peers();
+
+#[derive(Clone)]
+pub struct BitboardSudoku {
+    // Bit `i` of `planes[d]` is set when cell `i` holds digit `d + 1`
+    planes: [u128; 9],
+    // The digit of each cell as a single bit mask, 0 for empty cells
+    cells: [u16; 81],
+}
+
+impl BitboardSudoku {
+    pub fn empty() -> Self {
+        BitboardSudoku {
+            planes: [0; _],
+            cells: [0; _],
+        }
+    }
+}
+
+impl PuzzleCore for BitboardSudoku {
+    fn get(&self, index: u8) -> u8 {
+        (16 - self.cells[index as usize].leading_zeros()) as u8
+    }
+
+    fn set(&mut self, index: u8, val: u8) {
+        let bit = 1_u128 << index;
+        let old = self.cells[index as usize];
+        if old != 0 {
+            self.planes[old.trailing_zeros() as usize] &= !bit;
+        }
+        if val != 0 {
+            self.planes[val as usize - 1] |= bit;
+        }
+        self.cells[index as usize] = (1_u16 << val) >> 1;
+    }
+
+    fn get_candidates(&self, index: u8) -> CandidateSet {
+        let peers = PEERS[index as usize];
+        let mut seen = 0;
+        for (d, plane) in self.planes.iter().enumerate() {
+            seen |= ((plane & peers != 0) as u16) << d;
+        }
+        CandidateSet(!seen & 0b111111111)
+    }
+}
+
+impl PartialEq for BitboardSudoku {
+    fn eq(&self, other: &Self) -> bool {
+        self.planes == other.planes
+    }
+}
+impl Eq for BitboardSudoku {}
+
+impl MaybeValid for BitboardSudoku {
+    fn is_valid(&self) -> bool {
+        // Every digit fills exactly one cell of every unit
+        self.planes.iter().all(|plane| {
+            plane.count_ones() == 9
+                && UNITS.iter().all(|unit| {
+                    unit.iter()
+                        .filter(|index| plane & (1 << **index) != 0)
+                        .count()
+                        == 1
+                })
+        })
+    }
+}
+
+impl From<&Sudoku> for BitboardSudoku {
+    fn from(sudoku: &Sudoku) -> Self {
+        let mut bitboard = BitboardSudoku::empty();
+        for index in 0..81 {
+            bitboard.set(index, sudoku.get(index));
+        }
+        bitboard
+    }
+}
+
+impl From<&BitboardSudoku> for Sudoku {
+    fn from(bitboard: &BitboardSudoku) -> Self {
+        Sudoku::from(core::array::from_fn::<u8, 81, _>(|index| {
+            bitboard.get(index as u8)
+        }))
+    }
+}
+
+impl From<&str> for BitboardSudoku {
+    fn from(value: &str) -> Self {
+        (&Sudoku::from(value)).into()
+    }
+}
+
+impl From<[u8; 81]> for BitboardSudoku {
+    fn from(v: [u8; 81]) -> Self {
+        (&Sudoku::from(v)).into()
+    }
+}
+
+impl core::fmt::Display for BitboardSudoku {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        Sudoku::from(self).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pencil::units_of;
+
+    #[test]
+    fn test_matches_sudoku_core() {
+        let mut sudoku: Sudoku =
+            "000720030007006820106008709003091000580407200000000006840650010600143900005000402"
+                .into();
+        let mut bitboard = BitboardSudoku::from(&sudoku);
+        for (index, val) in [(0, 9), (1, 5), (0, 0), (80, 2), (80, 0), (40, 6)] {
+            sudoku.set(index, val);
+            bitboard.set(index, val);
+            for index in 0..81 {
+                assert_eq!(bitboard.get(index), sudoku.get(index));
+                if sudoku.is_missing(index) {
+                    assert!(bitboard.get_candidates(index) == sudoku.get_candidates(index));
+                }
+            }
+        }
+        for index in 0..81_u8 {
+            let peers = units_of(index)
+                .into_iter()
+                .flat_map(|unit| UNITS[unit])
+                .fold(0_u128, |acc, peer| acc | 1 << peer);
+            assert_eq!(PEERS[index as usize], peers & !(1 << index));
+        }
+        assert!(Sudoku::from(&bitboard) == sudoku);
+        assert!(!bitboard.is_valid());
+        assert!(
+            BitboardSudoku::from(
+                "958724631437916825126538749763291584581467293294385176849652317672143958315879462"
+            )
+            .is_valid()
+        );
+    }
+
+    #[test]
+    fn test_solves_with_bitboard_core() {
+        let solution: BitboardSudoku =
+            "265184937379562148481973526654318279813297465792456381137629854548731692926845713"
+                .into();
+        let did_solve = core::cell::Cell::new(false);
+        let mut bitboard: BitboardSudoku =
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003"
+                .into();
+        crate::solve_single_thread_dynamic(
+            &mut bitboard,
+            &crate::ALL_CANDIDATES,
+            |s| {
+                assert!(s.is_valid());
+                did_solve.set(*s == solution);
+                did_solve.get()
+            },
+            || false,
+        );
+        assert!(did_solve.get());
+    }
+}
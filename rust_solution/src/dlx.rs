@@ -0,0 +1,289 @@
+// Exact cover backend: Knuth's Algorithm X on dancing links.
+// Columns are the 324 constraints (cell filled, digit in row, digit in column, digit in subgrid),
+// rows are the (cell, digit) candidates. Constraints already satisfied by filled cells are left out.
+
+use crate::{CandidateSet, SolveProgress, SolverBackend, Sudoku};
+
+const COLUMNS: usize = 324;
+
+pub struct DancingLinks;
+
+impl SolverBackend for DancingLinks {
+    fn solve(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: &dyn Fn(&Sudoku) -> bool,
+        is_cancelled: &dyn Fn() -> bool,
+        check_interval: u32,
+    ) {
+        self.solve_with_progress(
+            sudoku,
+            allowed_candidates,
+            callback,
+            is_cancelled,
+            check_interval,
+            &|_| {},
+        );
+    }
+
+    fn solve_with_progress(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: &dyn Fn(&Sudoku) -> bool,
+        is_cancelled: &dyn Fn() -> bool,
+        check_interval: u32,
+        progress: &dyn Fn(&SolveProgress),
+    ) {
+        let mut matrix = Matrix::new(sudoku, allowed_candidates);
+        matrix.search(
+            sudoku,
+            &mut Search {
+                callback,
+                is_cancelled,
+                progress,
+                check_interval,
+                counter: 0,
+                nodes: 0,
+                frames: Vec::new(),
+            },
+        );
+    }
+
+    fn is_exhaustive(&self) -> bool {
+        true
+    }
+}
+
+// Everything a search passes down to its recursive calls
+struct Search<'a> {
+    callback: &'a dyn Fn(&Sudoku) -> bool,
+    is_cancelled: &'a dyn Fn() -> bool,
+    progress: &'a dyn Fn(&SolveProgress),
+    check_interval: u32,
+    counter: u32,
+    nodes: u64,
+    // (rows tried, rows) of the constraint branched on at each level
+    frames: Vec<(u32, u32)>,
+}
+
+// Node 0 is the root, nodes 1..=COLUMNS are the column headers, the rest are candidate nodes
+struct Matrix {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    column: Vec<usize>,
+    size: Vec<usize>,
+    candidate: Vec<(u8, u8)>,
+}
+
+fn constraint_columns(index: u8, digit: u8) -> [usize; 4] {
+    let digit = digit as usize - 1;
+    [
+        1 + index as usize,
+        1 + 81 + Sudoku::row_index(index) as usize * 9 + digit,
+        1 + 162 + Sudoku::col_index(index) as usize * 9 + digit,
+        1 + 243 + Sudoku::grid_index(index) as usize * 9 + digit,
+    ]
+}
+
+impl Matrix {
+    fn new(sudoku: &Sudoku, allowed_candidates: &[CandidateSet; 81]) -> Self {
+        let headers = COLUMNS + 1;
+        let mut matrix = Matrix {
+            left: (0..headers).map(|i| (i + headers - 1) % headers).collect(),
+            right: (0..headers).map(|i| (i + 1) % headers).collect(),
+            up: (0..headers).collect(),
+            down: (0..headers).collect(),
+            column: (0..headers).collect(),
+            size: vec![0; headers],
+            candidate: vec![(0, 0); headers],
+        };
+
+        let mut satisfied = [false; COLUMNS + 1];
+        for index in 0..81 {
+            if !sudoku.is_missing(index) {
+                for c in constraint_columns(index, sudoku.get(index)) {
+                    if !satisfied[c] {
+                        satisfied[c] = true;
+                        matrix.right[matrix.left[c]] = matrix.right[c];
+                        matrix.left[matrix.right[c]] = matrix.left[c];
+                    }
+                }
+            }
+        }
+
+        for index in 0..81 {
+            if sudoku.is_missing(index) {
+                for digit in sudoku.get_candidates(index) & allowed_candidates[index as usize] {
+                    matrix.add_row(index, digit);
+                }
+            }
+        }
+        matrix
+    }
+
+    fn add_row(&mut self, index: u8, digit: u8) {
+        let first = self.left.len();
+        for (k, c) in constraint_columns(index, digit).into_iter().enumerate() {
+            let node = first + k;
+            self.left.push(first + (k + 3) % 4);
+            self.right.push(first + (k + 1) % 4);
+            self.up.push(self.up[c]);
+            self.down.push(c);
+            self.column.push(c);
+            self.candidate.push((index, digit));
+            let last = self.up[c];
+            self.down[last] = node;
+            self.up[c] = node;
+            self.size[c] += 1;
+        }
+    }
+
+    fn cover(&mut self, c: usize) {
+        self.right[self.left[c]] = self.right[c];
+        self.left[self.right[c]] = self.left[c];
+        let mut i = self.down[c];
+        while i != c {
+            let mut j = self.right[i];
+            while j != i {
+                self.down[self.up[j]] = self.down[j];
+                self.up[self.down[j]] = self.up[j];
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, c: usize) {
+        let mut i = self.up[c];
+        while i != c {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                self.down[self.up[j]] = j;
+                self.up[self.down[j]] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        self.right[self.left[c]] = c;
+        self.left[self.right[c]] = c;
+    }
+
+    // Returns true once the search should stop, either because the callback accepted a solution
+    // or because it was cancelled
+    fn search(&mut self, sudoku: &mut Sudoku, search: &mut Search) -> bool {
+        if self.right[0] == 0 {
+            return (search.callback)(sudoku);
+        }
+
+        //Branch on the constraint with the fewest candidates left
+        let mut c = self.right[0];
+        let mut j = self.right[c];
+        while j != 0 && self.size[c] > 0 {
+            if self.size[j] < self.size[c] {
+                c = j;
+            }
+            j = self.right[j];
+        }
+        if self.size[c] == 0 {
+            return false;
+        }
+
+        self.cover(c);
+        let level = search.frames.len();
+        search.frames.push((0, self.size[c] as u32));
+        let mut r = self.down[c];
+        while r != c {
+            search.counter += 1;
+            if search.counter >= search.check_interval {
+                search.counter = 0;
+                if (search.is_cancelled)() {
+                    return true;
+                }
+                search.nodes += search.check_interval as u64;
+                (search.progress)(&SolveProgress::estimate(
+                    search.nodes,
+                    search.frames.iter().copied(),
+                ));
+            }
+
+            let (index, digit) = self.candidate[r];
+            sudoku.set(index, digit);
+            let mut j = self.right[r];
+            while j != r {
+                self.cover(self.column[j]);
+                j = self.right[j];
+            }
+            if self.search(sudoku, search) {
+                return true;
+            }
+            let mut j = self.left[r];
+            while j != r {
+                self.uncover(self.column[j]);
+                j = self.left[j];
+            }
+            sudoku.set(index, 0);
+            search.frames[level].0 += 1;
+            r = self.down[r];
+        }
+        search.frames.pop();
+        self.uncover(c);
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ALL_CANDIDATES, Backtracking, DEFAULT_CHECK_INTERVAL, DynamicBacktracking, MaybeValid,
+    };
+
+    #[test]
+    fn test_solves_and_counts() {
+        let mut sudoku: Sudoku =
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003"
+                .into();
+        let solution: Sudoku =
+            "265184937379562148481973526654318279813297465792456381137629854548731692926845713"
+                .into();
+        let did_solve = core::cell::Cell::new(false);
+        DancingLinks.solve(
+            &mut sudoku,
+            &ALL_CANDIDATES,
+            &|s| {
+                assert!(s.is_valid());
+                did_solve.set(*s == solution);
+                did_solve.get()
+            },
+            &|| false,
+            DEFAULT_CHECK_INTERVAL,
+        );
+        assert!(did_solve.get());
+
+        let unique: Sudoku =
+            "000720030007006820106008709003091000580407200000000006840650010600143900005000402"
+                .into();
+        assert_eq!(DancingLinks.count_solutions(&unique, usize::MAX), 1);
+
+        let ambiguous: Sudoku =
+            "000720030000000000106008709003091000580407200000000006840650010600143900005000402"
+                .into();
+        let count = DancingLinks.count_solutions(&ambiguous, usize::MAX);
+        assert!(count > 1);
+        assert_eq!(DancingLinks.count_solutions(&ambiguous, 2), 2);
+        assert_eq!(
+            Backtracking::default().count_solutions(&ambiguous, usize::MAX),
+            count
+        );
+        assert_eq!(
+            DynamicBacktracking.count_solutions(&ambiguous, usize::MAX),
+            count
+        );
+    }
+}
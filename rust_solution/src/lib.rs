@@ -0,0 +1,4874 @@
+#![feature(isolate_most_least_significant_one)]
+#![feature(unsafe_cell_access)]
+#![feature(thread_id_value)]
+#![feature(file_buffered)]
+#![feature(portable_simd)]
+
+// Run the solver on all csv files using `RUSTFLAGS="-Cprofile-use=$PWD/merged.profdata" cargo test --release -- --no-capture`
+// Print debug logs using `RUSTFLAGS='--cfg MULTITHREADING_DEBUG' cargo run --release`
+
+/*
+PGO helps, it reduced the runtime from 237s to 205s consistently
+Generate PGO using `RUSTFLAGS="-Cprofile-generate=/tmp/pgo-data" cargo test --release -- --no-capture`
+~/.rustup/toolchains/beta-aarch64-apple-darwin/lib/rustlib/aarch64-apple-darwin/bin/llvm-profdata  merge -o ./merged.profdata /tmp/pgo-data
+
+You can install llvm-profdata via: rustup component add llvm-tools-preview
+*/
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub mod backjump;
+pub mod bitboard;
+pub mod dlx;
+pub mod logic;
+pub mod parallel;
+pub mod pencil;
+pub mod simd;
+
+#[allow(unexpected_cfgs)]
+const MULTITHREADING_DEBUG: bool = cfg!(MULTITHREADING_DEBUG);
+#[allow(unexpected_cfgs)]
+const SUDOKU_DEBUG: bool = cfg!(SUDOKU_DEBUG);
+
+static INIT: Once = Once::new();
+
+thread_local! {
+    static THREAD_OUT_FILE: UnsafeCell<BufWriter<File>> = UnsafeCell::new(File::create_buffered(format!("thread{}.out", thread::current().id().as_u64())).unwrap());
+}
+
+macro_rules! thread_println {
+    ($($args:tt)*) => {
+        THREAD_OUT_FILE.with(|file|
+            unsafe { writeln!(file.as_mut_unchecked(), $($args)*).unwrap() }
+        );
+    };
+}
+
+struct TrustMeBro<T> {
+    inner: UnsafeCell<MaybeUninit<T>>,
+}
+impl<T> core::ops::Deref for TrustMeBro<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.inner.as_ref_unchecked().assume_init_ref() }
+    }
+}
+impl<T> TrustMeBro<T> {
+    const fn uninit() -> Self {
+        TrustMeBro {
+            inner: core::cell::UnsafeCell::new(core::mem::MaybeUninit::uninit()),
+        }
+    }
+    unsafe fn set(&self, val: T) {
+        unsafe { self.inner.as_mut_unchecked().write(val) };
+    }
+}
+unsafe impl<T: Sync> Sync for TrustMeBro<T> {}
+
+static PROGRAM_START_TIME: TrustMeBro<Instant> = TrustMeBro::uninit();
+
+pub trait MaybeValid {
+    fn is_valid(&self) -> bool;
+}
+impl MaybeValid for [u8; 9] {
+    fn is_valid(&self) -> bool {
+        let mut occurance = [false; 9];
+        for i in self {
+            if *i < 1 {
+                return false;
+            }
+            occurance[(*i - 1) as usize] = true
+        }
+        !occurance.contains(&false)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct CandidateSet(u16);
+impl CandidateSet {
+    fn new() -> Self {
+        CandidateSet(0b111111111)
+    }
+
+    pub fn empty() -> Self {
+        CandidateSet(0)
+    }
+
+    pub fn contains(&self, digit: u8) -> bool {
+        self.0 & (1 << (digit - 1)) != 0
+    }
+
+    pub fn len(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+/// Per-cell allowed digits that place no restriction beyond the givens
+pub const ALL_CANDIDATES: [CandidateSet; 81] = [CandidateSet(0b111111111); 81];
+
+#[derive(Copy, Clone)]
+pub struct CandidateSetIterator(u16);
+
+impl Iterator for CandidateSetIterator {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            None
+        } else {
+            let set_flag = self.0.isolate_least_significant_one();
+            self.0 ^= set_flag;
+            Some(set_flag.ilog2() as u8 + 1)
+        }
+    }
+}
+
+impl CandidateSetIterator {
+    fn empty() -> Self {
+        CandidateSetIterator(0)
+    }
+
+    fn fixed() -> Self {
+        CandidateSetIterator(u16::MAX)
+    }
+
+    fn is_fixed(&self) -> bool {
+        self.0 == u16::MAX
+    }
+
+    // Marks a cell filled by singles propagation, it is skipped like a fixed cell until undone
+    fn forced() -> Self {
+        CandidateSetIterator(u16::MAX - 1)
+    }
+
+    fn is_forced(&self) -> bool {
+        self.0 == u16::MAX - 1
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    // Digits left to try, not meaningful for fixed and forced cells
+    fn len(&self) -> u32 {
+        self.0.count_ones()
+    }
+}
+
+impl IntoIterator for CandidateSet {
+    type Item = u8;
+    type IntoIter = CandidateSetIterator;
+
+    fn into_iter(self) -> Self::IntoIter {
+        CandidateSetIterator(self.0)
+    }
+}
+
+impl core::ops::BitAndAssign<u16> for CandidateSet {
+    fn bitand_assign(&mut self, rhs: u16) {
+        self.0 &= rhs;
+    }
+}
+
+impl core::ops::BitOrAssign<u16> for CandidateSet {
+    fn bitor_assign(&mut self, rhs: u16) {
+        self.0 |= rhs;
+    }
+}
+
+impl core::ops::BitAnd<CandidateSet> for CandidateSet {
+    type Output = CandidateSet;
+
+    fn bitand(self, rhs: CandidateSet) -> Self::Output {
+        CandidateSet(self.0 & rhs.0)
+    }
+}
+
+#[derive(Clone)]
+pub struct Sudoku {
+    grid: [u8; 41],
+    row_candidates: [CandidateSet; 9],
+    col_candidates: [CandidateSet; 9],
+    grid_candidates: [CandidateSet; 9],
+}
+
+impl PartialEq for Sudoku {
+    fn eq(&self, other: &Self) -> bool {
+        self.grid == other.grid
+    }
+}
+impl Eq for Sudoku {}
+
+impl core::hash::Hash for Sudoku {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.grid.hash(state)
+    }
+}
+
+impl Sudoku {
+    pub fn get(&self, index: u8) -> u8 {
+        let val = self.grid[(index >> 1) as usize];
+        if index & 1 == 1 {
+            val >> 4
+        } else {
+            val & 0b1111
+        }
+    }
+
+    fn is_missing(&self, index: u8) -> bool {
+        self.get(index) == 0
+    }
+
+    fn set(&mut self, index: u8, val: u8) {
+        let oldval;
+        let oldvalpair = &mut self.grid[(index >> 1) as usize];
+        let row_candidate_set = &mut self.row_candidates[Self::row_index(index) as usize];
+        let col_candidate_set = &mut self.col_candidates[Self::col_index(index) as usize];
+        let grid_candidate_set = &mut self.grid_candidates[Self::grid_index(index) as usize];
+
+        if index & 1 == 1 {
+            oldval = *oldvalpair >> 4;
+            *oldvalpair = *oldvalpair & 0b1111 | val << 4;
+        } else {
+            oldval = *oldvalpair & 0b1111;
+            *oldvalpair = (*oldvalpair & 0b11110000) | val;
+        }
+
+        if oldval != 0 {
+            let bit_filter = 1 << (oldval - 1);
+            *row_candidate_set |= bit_filter;
+            *col_candidate_set |= bit_filter;
+            *grid_candidate_set |= bit_filter;
+        }
+        if val != 0 {
+            let bit_filter = !(1 << (val - 1));
+            *row_candidate_set &= bit_filter;
+            *col_candidate_set &= bit_filter;
+            *grid_candidate_set &= bit_filter;
+        }
+    }
+
+    /*
+    00 01 02 03 04 05 06 07 08
+    09 10 11 12 13 14 15 16 17
+    18 19 20 21 22 23 24 25 26
+    27 28 29 30 31 32 33 34 35
+    36 37 38 39 40 41 42 43 44
+    45 46 47 48 49 50 51 52 53
+    54 55 56 57 58 59 60 61 62
+    63 64 65 66 67 68 69 70 71
+    72 73 74 75 76 77 78 79 80
+
+    bash commands:
+    grid_from() { local x=$1; echo $x, $(($x+1)), $(($x+2)), $(($x+9)), $(($x+10)), $(($x+11)), $(($x+18)), $(($x+19)), $(($x+20)) ; }
+    */
+
+    fn grid_index(index: u8) -> u8 {
+        let row_num = index / 27;
+        let col_num = (index % 9) / 3;
+        row_num * 3 + col_num
+    }
+
+    fn row_index(index: u8) -> u8 {
+        index / 9
+    }
+
+    fn col_index(index: u8) -> u8 {
+        index % 9
+    }
+
+    pub fn get_candidates(&self, index: u8) -> CandidateSet {
+        let row_candidate_set = self.row_candidates[Self::row_index(index) as usize];
+        let col_candidate_set = self.col_candidates[Self::col_index(index) as usize];
+        let grid_candidate_set = self.grid_candidates[Self::grid_index(index) as usize];
+        col_candidate_set & row_candidate_set & grid_candidate_set
+    }
+}
+
+/// The cell level operations the backtracking solvers need, so alternative puzzle representations
+/// can be benchmarked against `Sudoku`
+pub trait PuzzleCore {
+    fn get(&self, index: u8) -> u8;
+    fn set(&mut self, index: u8, val: u8);
+    // Only meaningful for empty cells
+    fn get_candidates(&self, index: u8) -> CandidateSet;
+
+    fn is_missing(&self, index: u8) -> bool {
+        self.get(index) == 0
+    }
+}
+
+impl PuzzleCore for Sudoku {
+    fn get(&self, index: u8) -> u8 {
+        Sudoku::get(self, index)
+    }
+
+    fn set(&mut self, index: u8, val: u8) {
+        Sudoku::set(self, index, val)
+    }
+
+    fn get_candidates(&self, index: u8) -> CandidateSet {
+        Sudoku::get_candidates(self, index)
+    }
+}
+
+impl MaybeValid for Sudoku {
+    fn is_valid(&self) -> bool {
+        for i in 0..9 {
+            if ![
+                self.get(i * 9),
+                self.get(i * 9 + 1),
+                self.get(i * 9 + 2),
+                self.get(i * 9 + 3),
+                self.get(i * 9 + 4),
+                self.get(i * 9 + 5),
+                self.get(i * 9 + 6),
+                self.get(i * 9 + 7),
+                self.get(i * 9 + 8),
+            ]
+            .is_valid()
+            {
+                return false;
+            }
+
+            if ![
+                self.get(i),
+                self.get(i + 9),
+                self.get(i + 9 * 2),
+                self.get(i + 9 * 3),
+                self.get(i + 9 * 4),
+                self.get(i + 9 * 5),
+                self.get(i + 9 * 6),
+                self.get(i + 9 * 7),
+                self.get(i + 9 * 8),
+            ]
+            .is_valid()
+            {
+                return false;
+            }
+        }
+
+        for i in [0, 3, 6, 27, 30, 33, 54, 57, 60] {
+            if ![
+                self.get(i),
+                self.get(i + 1),
+                self.get(i + 2),
+                self.get(i + 9),
+                self.get(i + 10),
+                self.get(i + 11),
+                self.get(i + 18),
+                self.get(i + 19),
+                self.get(i + 20),
+            ]
+            .is_valid()
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl From<[u8; 81]> for Sudoku {
+    fn from(v: [u8; 81]) -> Self {
+        let mut sudoku = Sudoku {
+            grid: [0; _],
+            row_candidates: [CandidateSet::new(); _],
+            col_candidates: [CandidateSet::new(); _],
+            grid_candidates: [CandidateSet::new(); _],
+        };
+        for (k, v) in v.into_iter().enumerate() {
+            if v != 0 {
+                sudoku.set(k as u8, v);
+            }
+        }
+        sudoku
+    }
+}
+
+impl From<[u8; 41]> for Sudoku {
+    fn from(v: [u8; 41]) -> Self {
+        let mut sudoku = Sudoku {
+            grid: [0; _],
+            row_candidates: [CandidateSet::new(); _],
+            col_candidates: [CandidateSet::new(); _],
+            grid_candidates: [CandidateSet::new(); _],
+        };
+        for (k, v) in v.into_iter().enumerate() {
+            let v1 = v & 0b1111;
+            let v2 = v >> 4;
+            if v1 != 0 {
+                sudoku.set((k * 2) as u8, v1);
+            }
+            if v2 != 0 && k != 40 {
+                sudoku.set((k * 2 + 1) as u8, v2);
+            }
+        }
+        sudoku
+    }
+}
+
+impl From<&str> for Sudoku {
+    fn from(value: &str) -> Self {
+        let mut sudoku = Sudoku {
+            grid: [0; _],
+            row_candidates: [CandidateSet::new(); _],
+            col_candidates: [CandidateSet::new(); _],
+            grid_candidates: [CandidateSet::new(); _],
+        };
+        for (k, v) in value.as_bytes().iter().enumerate() {
+            let digit = *v - b'0';
+            if digit != 0_u8 {
+                sudoku.set(k as u8, digit);
+            }
+        }
+        sudoku
+    }
+}
+
+impl core::fmt::Display for Sudoku {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for i in 0..9 {
+            "\n".fmt(f)?;
+            for j in 0..9 {
+                let val = self.get(i * 9 + j);
+                if val == 0 {
+                    "?".fmt(f)?;
+                } else {
+                    val.fmt(f)?;
+                }
+                ", ".fmt(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct SingleLineDisplayAdaptor<'a, T>(&'a T);
+impl<P: PuzzleCore> core::fmt::Display for SingleLineDisplayAdaptor<'_, P> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for i in 0..9 {
+            // "\n".fmt(f)?;
+            for j in 0..9 {
+                let val = self.0.get(i * 9 + j);
+                if val == 0 {
+                    "?".fmt(f)?;
+                } else {
+                    val.fmt(f)?;
+                }
+                // ", ".fmt(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An order in which the backtracking search visits the cells, `cells[k]` is the k-th cell tried
+#[derive(Clone, Copy)]
+pub struct CellOrdering {
+    pub name: &'static str,
+    pub cells: [u8; 81],
+}
+
+const fn build_row_wise() -> [u8; 81] {
+    let mut cells = [0; 81];
+    let mut i = 0;
+    while i < 81 {
+        cells[i] = i as u8;
+        i += 1;
+    }
+    cells
+}
+
+const fn reversed(cells: [u8; 81]) -> [u8; 81] {
+    let mut out = [0; 81];
+    let mut i = 0;
+    while i < 81 {
+        out[i] = cells[80 - i];
+        i += 1;
+    }
+    out
+}
+
+const ROW_WISE: [u8; 81] = build_row_wise();
+const SUBGRID_WISE: [u8; 81] = [
+    0, 1, 2, 9, 10, 11, 18, 19, 20, 27, 28, 29, 36, 37, 38, 45, 46, 47, 54, 55, 56, 63, 64, 65, 72,
+    73, 74, 3, 4, 5, 12, 13, 14, 21, 22, 23, 30, 31, 32, 39, 40, 41, 48, 49, 50, 57, 58, 59, 66,
+    67, 68, 75, 76, 77, 6, 7, 8, 15, 16, 17, 24, 25, 26, 33, 34, 35, 42, 43, 44, 51, 52, 53, 60,
+    61, 62, 69, 70, 71, 78, 79, 80,
+];
+const COL_WISE: [u8; 81] = [
+    0, 9, 18, 27, 36, 45, 54, 63, 72, 1, 10, 19, 28, 37, 46, 55, 64, 73, 2, 11, 20, 29, 38, 47, 56,
+    65, 74, 3, 12, 21, 30, 39, 48, 57, 66, 75, 4, 13, 22, 31, 40, 49, 58, 67, 76, 5, 14, 23, 32,
+    41, 50, 59, 68, 77, 6, 15, 24, 33, 42, 51, 60, 69, 78, 7, 16, 25, 34, 43, 52, 61, 70, 79, 8,
+    17, 26, 35, 44, 53, 62, 71, 80,
+];
+
+/// The orderings `with_multithreaded_solver` races against each other. The main thread starts with
+/// the first one, helper thread `k` with ordering `k`. Threads beyond these search in random orders.
+pub const CELL_ORDERINGS: [CellOrdering; 9] = [
+    CellOrdering {
+        name: "row-wise",
+        cells: ROW_WISE,
+    },
+    CellOrdering {
+        name: "reverse row-wise",
+        cells: reversed(ROW_WISE),
+    },
+    CellOrdering {
+        name: "subgrid-wise",
+        cells: SUBGRID_WISE,
+    },
+    CellOrdering {
+        name: "column-wise",
+        cells: COL_WISE,
+    },
+    CellOrdering {
+        name: "reverse column-wise",
+        cells: reversed(COL_WISE),
+    },
+    CellOrdering {
+        name: "reverse subgrid-wise",
+        cells: reversed(SUBGRID_WISE),
+    },
+    CellOrdering {
+        name: "random 1",
+        cells: [
+            20, 66, 78, 77, 39, 68, 57, 69, 65, 74, 13, 19, 60, 38, 23, 53, 5, 6, 12, 73, 59, 51,
+            30, 58, 80, 24, 0, 9, 42, 64, 52, 41, 61, 21, 31, 27, 17, 67, 33, 62, 4, 11, 63, 48,
+            10, 70, 34, 2, 44, 45, 46, 1, 29, 15, 26, 16, 7, 56, 71, 35, 40, 28, 37, 76, 25, 43,
+            79, 54, 49, 14, 50, 72, 36, 18, 55, 75, 3, 8, 47, 22, 32,
+        ],
+    },
+    CellOrdering {
+        name: "random 2",
+        cells: [
+            10, 38, 58, 73, 19, 52, 72, 68, 55, 31, 63, 43, 13, 65, 20, 60, 57, 5, 80, 79, 16, 17,
+            44, 39, 4, 25, 14, 32, 23, 27, 66, 9, 24, 71, 70, 8, 61, 0, 21, 30, 56, 33, 74, 59, 3,
+            64, 54, 69, 37, 40, 28, 2, 77, 78, 46, 42, 6, 15, 7, 76, 22, 1, 35, 11, 45, 51, 36, 50,
+            62, 41, 49, 26, 12, 67, 29, 18, 48, 34, 47, 75, 53,
+        ],
+    },
+    CellOrdering {
+        name: "random 3",
+        cells: [
+            41, 79, 23, 76, 29, 35, 36, 28, 52, 34, 37, 7, 43, 38, 59, 48, 50, 62, 24, 3, 14, 10,
+            30, 40, 5, 64, 11, 25, 31, 26, 69, 33, 61, 78, 77, 70, 1, 12, 13, 74, 42, 58, 22, 16,
+            20, 4, 57, 27, 15, 39, 63, 49, 56, 21, 60, 51, 53, 46, 44, 72, 73, 67, 68, 75, 2, 6,
+            32, 19, 66, 8, 17, 47, 80, 65, 0, 71, 54, 55, 9, 45, 18,
+        ],
+    },
+];
+
+// SplitMix64, good enough to shuffle cell orders
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// A shuffled cell order, the same seed always gives the same order
+pub fn random_cell_order(seed: u64) -> [u8; 81] {
+    let mut state = seed;
+    let mut cells = ROW_WISE;
+    for k in (1..81).rev() {
+        cells.swap(k, (next_random(&mut state) % (k as u64 + 1)) as usize);
+    }
+    cells
+}
+
+// The Luby sequence 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, ... starting at i = 1
+fn luby(mut i: u64) -> u64 {
+    loop {
+        let k = 64 - i.leading_zeros();
+        if i == (1 << k) - 1 {
+            return 1 << (k - 1);
+        }
+        i -= (1 << (k - 1)) - 1;
+    }
+}
+
+/// Seed of the random orderings used by threads beyond the ones `CELL_ORDERINGS` has room for
+pub const DEFAULT_ORDERING_SEED: u64 = 0x5EED;
+
+/// Searches every thread in a random cell order instead of one of `CELL_ORDERINGS`, see
+/// `Solver::set_random_orderings`
+#[derive(Clone, Copy)]
+pub struct RandomOrderings {
+    /// Every thread, problem and restart derives its own order from this
+    pub seed: u64,
+    /// Search nodes before a thread gives up on its order and restarts with a new one. The budget
+    /// of later restarts grows along the Luby sequence, so a run eventually gets as many nodes as
+    /// it needs. None never restarts.
+    pub restart_base: Option<u64>,
+}
+
+/// How often an ordering of `CELL_ORDERINGS` was searched, and how often it found the accepted
+/// solution before the other threads
+#[derive(Clone, Copy, Debug)]
+pub struct OrderingStats {
+    pub name: &'static str,
+    pub runs: u32,
+    pub wins: u32,
+}
+
+impl OrderingStats {
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.runs.max(1) as f64
+    }
+
+    // Win rate counting one extra win and one extra loss, so orderings that never ran still get a turn
+    fn score(&self) -> f64 {
+        (self.wins + 1) as f64 / (self.runs + 2) as f64
+    }
+}
+
+struct SharedContext {
+    current_problem: Sudoku,
+    current_allowed_candidates: [CandidateSet; 81],
+    //Index into CELL_ORDERINGS per thread, slot 0 is the main thread
+    slot_orderings: Vec<usize>,
+    ordering_stats: [OrderingStats; 9],
+    adaptive_orderings: bool,
+    random_orderings: Option<RandomOrderings>,
+    //Backend per thread, threads past the end search by cell ordering
+    portfolio: Vec<Arc<dyn SolverBackend>>,
+    current_limits: SolveLimits,
+    //Progress of the live problem, reported by the thread that called `solve`
+    progress_callback: Option<ProgressCallback>,
+    //Submitted jobs no thread has picked up yet
+    jobs: VecDeque<Job>,
+    next_job_id: u64,
+}
+
+// The callback of the live problem and what it kept. It has a lock of its own, so a callback can
+// call into the pool, to submit a job say, without waiting for the lock it was called with.
+struct CallbackState<SOLFN> {
+    solution_callback: Option<SOLFN>, //None value signifies that problem is solved
+    //Solutions of the live problem the callback accepted or recorded
+    found: Solutions,
+    //The recorded solutions of `found` again, so no thread hands one of them over twice
+    recorded: HashSet<Sudoku>,
+    //Set if the callback or a backend panicked on the live problem
+    callback_panic: Option<CallbackPanic>,
+}
+
+impl SharedContext {
+    //Gives the orderings with the best win rate so far to the threads
+    fn reassign_orderings(&mut self) {
+        let mut ranked: [usize; 9] = core::array::from_fn(|k| k);
+        ranked.sort_by(|a, b| {
+            self.ordering_stats[*b]
+                .score()
+                .total_cmp(&self.ordering_stats[*a].score())
+        });
+        let slots = self.slot_orderings.len();
+        self.slot_orderings.copy_from_slice(&ranked[..slots]);
+    }
+}
+
+// The search loop only reads the atomics, the mutex is taken to pick up a new problem and by a
+// thread holding a solution for the live problem. The atomics are only written with the lock held,
+// so idle threads waiting on `new_problem` cannot miss an update. A thread needing both locks takes
+// `callback` first.
+struct SharedState<SOLFN> {
+    context: Mutex<SharedContext>,
+    callback: Mutex<CallbackState<SOLFN>>,
+    //Idle helper threads sleep on this until a new problem is queued or they are shut down
+    new_problem: Condvar,
+    //Index of the current problem, -1 tells the helper threads to shut down
+    generation: AtomicI32,
+    //Set once a solution was accepted or a thread searched the problem to the end
+    solved: AtomicBool,
+    //Search nodes between two cancellation checks
+    check_interval: AtomicU32,
+    //Statistics of the live problem, nodes are added up at every cancellation check
+    nodes: AtomicU64,
+    solutions: AtomicU64,
+    limit_reached: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+impl<SOLFN> SharedState<SOLFN> {
+    fn new(helper_threads: usize) -> Self {
+        SharedState {
+            context: Mutex::new(SharedContext {
+                current_problem: Sudoku::from("0"),
+                current_allowed_candidates: ALL_CANDIDATES,
+                slot_orderings: (0..(helper_threads + 1).min(CELL_ORDERINGS.len())).collect(),
+                ordering_stats: CELL_ORDERINGS.map(|ordering| OrderingStats {
+                    name: ordering.name,
+                    runs: 0,
+                    wins: 0,
+                }),
+                adaptive_orderings: true,
+                random_orderings: None,
+                portfolio: Vec::new(),
+                current_limits: SolveLimits::default(),
+                progress_callback: None,
+                jobs: VecDeque::new(),
+                next_job_id: 0,
+            }),
+            callback: Mutex::new(CallbackState {
+                solution_callback: None,
+                found: Solutions::default(),
+                recorded: HashSet::new(),
+                callback_panic: None,
+            }),
+            new_problem: Condvar::new(),
+            generation: AtomicI32::new(0),
+            solved: AtomicBool::new(true),
+            check_interval: AtomicU32::new(DEFAULT_CHECK_INTERVAL),
+            nodes: AtomicU64::new(0),
+            solutions: AtomicU64::new(0),
+            limit_reached: AtomicBool::new(false),
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    fn is_stale(&self, generation: i32) -> bool {
+        self.solved.load(Ordering::Acquire) || self.generation.load(Ordering::Acquire) != generation
+    }
+
+    //Stops every thread once the limits of the live problem ran out or it was cancelled, `reason`
+    //is the flag telling `solve` which one it was
+    fn stop_early(&self, generation: i32, reason: &AtomicBool) {
+        let _shared_context = self.context.lock().unwrap();
+        if !self.is_stale(generation) {
+            reason.store(true, Ordering::Release);
+            self.solved.store(true, Ordering::Release);
+        }
+    }
+
+    //Stops every thread once a backend or the progress callback panicked on the live problem, `solve`
+    //returns the panic
+    fn fail(&self, generation: i32, panic: CallbackPanic) {
+        let mut callback_state = self.callback.lock().unwrap();
+        let _shared_context = self.context.lock().unwrap();
+        if !self.is_stale(generation) {
+            callback_state.callback_panic = Some(panic);
+            callback_state.solution_callback = None;
+            self.solved.store(true, Ordering::Release);
+        }
+    }
+
+    fn shut_down(&self) {
+        let _shared_context = self.context.lock().unwrap_or_else(PoisonError::into_inner);
+        self.generation.store(-1, Ordering::Release);
+        self.new_problem.notify_all();
+    }
+}
+
+// Takes the callback back out of the shared state when `solve` returns or unwinds. Helper threads
+// only call it with its lock held, so none of them can call it afterwards.
+struct RetireCallback<'a, SOLFN>(&'a SharedState<SOLFN>);
+
+impl<SOLFN> Drop for RetireCallback<'_, SOLFN> {
+    fn drop(&mut self) {
+        let callback = {
+            let mut callback_state = self
+                .0
+                .callback
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let _shared_context = self
+                .0
+                .context
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            //Also stops helpers still searching a problem the calling thread exhausted
+            self.0.solved.store(true, Ordering::Release);
+            callback_state.solution_callback.take()
+        };
+        drop(callback);
+    }
+}
+
+impl<SOLFN, R> SharedState<SOLFN>
+where
+    SOLFN: FnMut(&Sudoku) -> R + std::marker::Send,
+    R: Into<SolutionAction>,
+{
+    fn solve(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: SOLFN,
+        limits: &SolveLimits,
+    ) -> Result<(SolveOutcome, Solutions), CallbackPanic> {
+        let start = Instant::now();
+        // unsafe { PROGRAM_START_TIME.set(Instant::now()); }
+        if MULTITHREADING_DEBUG {
+            thread_println!(
+                "{:?}: Queueing next problem with main thread: {:?}",
+                PROGRAM_START_TIME.elapsed().as_nanos(),
+                thread::current().id()
+            );
+        }
+        {
+            //Threads still on the last problem see it solved, so they do not call the new callback
+            let mut callback_state = self.callback.lock().unwrap();
+            callback_state.solution_callback = Some(callback);
+            callback_state.found = Solutions::default();
+            callback_state.recorded.clear();
+            callback_state.callback_panic = None;
+        }
+        {
+            let mut shared_context = self.context.lock().unwrap();
+            if shared_context.adaptive_orderings {
+                shared_context.reassign_orderings();
+            }
+            shared_context.current_problem = sudoku.clone();
+            shared_context.current_allowed_candidates = *allowed_candidates;
+            shared_context.current_limits = limits.clone();
+            self.nodes.store(0, Ordering::Relaxed);
+            self.solutions.store(0, Ordering::Relaxed);
+            self.limit_reached.store(false, Ordering::Release);
+            self.cancelled.store(false, Ordering::Release);
+            let generation = self.generation.load(Ordering::Relaxed) + 1;
+            self.generation.store(generation, Ordering::Release);
+            self.solved.store(false, Ordering::Release);
+            self.new_problem.notify_all();
+            if MULTITHREADING_DEBUG {
+                thread_println!(
+                    "{:?}: Queued problem {} with main thread: {:?}",
+                    PROGRAM_START_TIME.elapsed().as_nanos(),
+                    generation,
+                    thread::current().id()
+                );
+            }
+        }
+        let _retire = RetireCallback(self);
+        if MULTITHREADING_DEBUG {
+            thread_println!(
+                "{:?}: Started work on the problem in main thread: {:?}",
+                PROGRAM_START_TIME.elapsed().as_nanos(),
+                thread::current().id()
+            );
+        }
+        multithreaded_helper::<false, _, _>(self, 0);
+
+        //Waits for a callback another thread may still be in
+        let found = {
+            let mut callback_state = self.callback.lock().unwrap();
+            if let Some(callback_panic) = callback_state.callback_panic.take() {
+                return Err(callback_panic);
+            }
+            core::mem::take(&mut callback_state.found)
+        };
+        let stats = SolveStats {
+            nodes: self.nodes.load(Ordering::Relaxed),
+            solutions: self.solutions.load(Ordering::Relaxed),
+            elapsed: start.elapsed(),
+        };
+        let outcome = if self.limit_reached.load(Ordering::Acquire) {
+            SolveOutcome::LimitReached(stats)
+        } else if self.cancelled.load(Ordering::Acquire) {
+            SolveOutcome::Cancelled(stats)
+        } else {
+            SolveOutcome::Finished(stats)
+        };
+        Ok((outcome, found))
+    }
+
+    fn ordering_stats(&self) -> [OrderingStats; 9] {
+        self.context.lock().unwrap().ordering_stats
+    }
+
+    fn set_random_orderings(&self, random_orderings: Option<RandomOrderings>) {
+        self.context.lock().unwrap().random_orderings = random_orderings;
+    }
+
+    fn set_portfolio(&self, portfolio: Vec<Arc<dyn SolverBackend>>) {
+        self.context.lock().unwrap().portfolio = portfolio;
+    }
+
+    fn set_adaptive_orderings(&self, adaptive: bool) {
+        self.context.lock().unwrap().adaptive_orderings = adaptive;
+    }
+
+    fn set_progress_callback(&self, callback: Option<ProgressCallback>) {
+        self.context.lock().unwrap().progress_callback = callback;
+    }
+
+    fn set_check_interval(&self, nodes: u32) {
+        self.check_interval.store(nodes.max(1), Ordering::Relaxed);
+    }
+}
+
+pub struct Solver<'a, SOLFN> {
+    shared_state: &'a SharedState<SOLFN>,
+}
+
+impl<'a, SOLFN, R> Solver<'a, SOLFN>
+where
+    SOLFN: FnMut(&Sudoku) -> R + std::marker::Send,
+    R: Into<SolutionAction>,
+{
+    /// Hands solutions to `callback` until it accepts one or aborts. The threads race for the
+    /// same solutions, so a rejected solution may be handed to it again, but a recorded one never
+    /// is. If `callback` or a backend of the portfolio panics, whichever thread called it, the
+    /// search stops and the panic is returned as an error.
+    pub fn solve(
+        &mut self,
+        sudoku: &mut Sudoku,
+        callback: SOLFN,
+    ) -> Result<Solutions, CallbackPanic> {
+        self.solve_with_candidates(sudoku, &ALL_CANDIDATES, callback)
+    }
+
+    /// Like `solve`, but each empty cell may only take the digits in its `allowed_candidates` mask
+    pub fn solve_with_candidates(
+        &mut self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: SOLFN,
+    ) -> Result<Solutions, CallbackPanic> {
+        self.solve_with_limits(
+            sudoku,
+            allowed_candidates,
+            callback,
+            &SolveLimits::default(),
+        )
+        .map(|(_, solutions)| solutions)
+    }
+
+    /// Like `solve_with_candidates`, but gives up once `limits` run out
+    pub fn solve_with_limits(
+        &mut self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: SOLFN,
+        limits: &SolveLimits,
+    ) -> Result<(SolveOutcome, Solutions), CallbackPanic> {
+        self.shared_state
+            .solve(sudoku, allowed_candidates, callback, limits)
+    }
+
+    /// Runs and wins of every ordering over the problems solved so far
+    pub fn ordering_stats(&self) -> [OrderingStats; 9] {
+        self.shared_state.ordering_stats()
+    }
+
+    /// Searches in seeded random orders, optionally restarting with a new order once a growing node
+    /// budget runs out. Win rates are only recorded for `CELL_ORDERINGS`, so random orders do not
+    /// show up in `ordering_stats`. Takes effect from the next problem on.
+    pub fn set_random_orderings(&mut self, random_orderings: Option<RandomOrderings>) {
+        self.shared_state.set_random_orderings(random_orderings);
+    }
+
+    /// Runs `portfolio[k]` on thread `k` instead of a cell ordering, the calling thread being thread
+    /// 0. Threads past the end of the portfolio keep searching by cell ordering, and whichever thread
+    /// finds the accepted solution first cancels the others. Takes effect from the next problem on.
+    pub fn set_portfolio(&mut self, portfolio: Vec<Arc<dyn SolverBackend>>) {
+        self.shared_state.set_portfolio(portfolio);
+    }
+
+    /// With adaptive orderings, which is the default, every new problem hands the threads the
+    /// orderings that won most often so far. Otherwise each thread keeps its ordering.
+    pub fn set_adaptive_orderings(&mut self, adaptive: bool) {
+        self.shared_state.set_adaptive_orderings(adaptive);
+    }
+
+    /// Calls `callback` every check interval while the thread that called `solve` searches, by cell
+    /// ordering or with its backend from the portfolio. The node count covers all threads, depth
+    /// and estimate are those of the calling thread, 0 if its backend cannot estimate them. Takes
+    /// effect from the next problem on.
+    pub fn set_progress_callback(&mut self, callback: Option<ProgressCallback>) {
+        self.shared_state.set_progress_callback(callback);
+    }
+
+    /// Number of search nodes every thread visits between two checks whether its problem was
+    /// solved or replaced. Takes effect from the next problem on.
+    pub fn set_check_interval(&mut self, nodes: u32) {
+        self.shared_state.set_check_interval(nodes);
+    }
+}
+
+fn multithreaded_helper<
+    const STAY_ALIVE: bool,
+    SOLFN: FnMut(&Sudoku) -> R + std::marker::Send,
+    R: Into<SolutionAction>,
+>(
+    shared_state: &SharedState<SOLFN>,
+    slot: usize,
+) {
+    let mut local_last_known_problem_index = 0;
+    let mut local_last_known_problem;
+    let mut local_allowed_candidates;
+    let mut local_ordering;
+    let mut local_random;
+    let mut local_backend;
+    let mut local_limits;
+    let mut local_progress;
+    loop {
+        if MULTITHREADING_DEBUG {
+            thread_println!(
+                "{:?}: Thread {:?} is checking for new tasks...",
+                PROGRAM_START_TIME.elapsed().as_nanos(),
+                thread::current().id()
+            );
+        }
+        {
+            let mut shared_context = shared_state.context.lock().unwrap();
+            if STAY_ALIVE {
+                //Sleep until there is a problem this thread has not worked on yet, an unsolvable
+                //problem would otherwise be searched again and again
+                shared_context = shared_state
+                    .new_problem
+                    .wait_while(shared_context, |shared_context| {
+                        let generation = shared_state.generation.load(Ordering::Acquire);
+                        generation != -1
+                            && (shared_state.solved.load(Ordering::Acquire)
+                                || generation == local_last_known_problem_index)
+                            && shared_context.jobs.is_empty()
+                    })
+                    .unwrap();
+            }
+            let shared_context_current_problem_index =
+                shared_state.generation.load(Ordering::Acquire);
+            if shared_context_current_problem_index == -1 {
+                if MULTITHREADING_DEBUG {
+                    thread_println!(
+                        "{:?}: Thread {:?} acknowledged order to shut down",
+                        PROGRAM_START_TIME.elapsed().as_nanos(),
+                        thread::current().id()
+                    );
+                }
+                break;
+            }
+            if STAY_ALIVE
+                && (shared_state.solved.load(Ordering::Acquire)
+                    || shared_context_current_problem_index == local_last_known_problem_index)
+            {
+                //Woken up for a queued job, a live problem would have gone first
+                let job = shared_context.jobs.pop_front().unwrap();
+                let backend = shared_context.portfolio.get(slot).cloned();
+                drop(shared_context);
+                if MULTITHREADING_DEBUG {
+                    thread_println!(
+                        "{:?}: Thread {:?} picked up job {}",
+                        PROGRAM_START_TIME.elapsed().as_nanos(),
+                        thread::current().id(),
+                        job.state.id
+                    );
+                }
+                job.run(
+                    backend.as_deref().unwrap_or(&DynamicBacktracking),
+                    shared_state.check_interval.load(Ordering::Relaxed),
+                    || shared_state.generation.load(Ordering::Acquire) == -1,
+                );
+                continue;
+            }
+            if shared_state.solved.load(Ordering::Acquire) {
+                if MULTITHREADING_DEBUG {
+                    thread_println!(
+                        "{:?}: Thread {:?} found no new tasks",
+                        PROGRAM_START_TIME.elapsed().as_nanos(),
+                        thread::current().id()
+                    );
+                }
+                //Only the main thread gets here, helper threads wait for new work above
+                if MULTITHREADING_DEBUG {
+                    thread_println!(
+                        "{:?}: Main Thread {:?} was late to the party, yielding control to caller.",
+                        PROGRAM_START_TIME.elapsed().as_nanos(),
+                        thread::current().id()
+                    );
+                }
+                break;
+            }
+            local_last_known_problem_index = shared_context_current_problem_index;
+            local_last_known_problem = shared_context.current_problem.clone();
+            local_allowed_candidates = shared_context.current_allowed_candidates;
+            local_backend = shared_context.portfolio.get(slot).cloned();
+            local_limits = shared_context.current_limits.clone();
+            local_progress = shared_context
+                .progress_callback
+                .clone()
+                .filter(|_| slot == 0);
+            //Threads without a fixed ordering of their own search in seeded random orders
+            local_ordering = shared_context
+                .slot_orderings
+                .get(slot)
+                .copied()
+                .filter(|_| shared_context.random_orderings.is_none() && local_backend.is_none());
+            local_random = shared_context.random_orderings.unwrap_or(RandomOrderings {
+                seed: DEFAULT_ORDERING_SEED,
+                restart_base: None,
+            });
+            if let Some(ordering) = local_ordering {
+                shared_context.ordering_stats[ordering].runs += 1;
+            }
+            if MULTITHREADING_DEBUG {
+                thread_println!(
+                    "{:?}: Thread {:?} found work, will start work on {}",
+                    PROGRAM_START_TIME.elapsed().as_nanos(),
+                    thread::current().id(),
+                    local_last_known_problem_index
+                );
+            }
+        }
+
+        let on_solution = |solved_sudoku: &Sudoku| {
+            //Losing threads never touch the lock
+            if shared_state.is_stale(local_last_known_problem_index) {
+                if MULTITHREADING_DEBUG {
+                    thread_println!(
+                        "{:?}: Thread {:?} CANCELLING problem {} [via on_solved]",
+                        PROGRAM_START_TIME.elapsed().as_nanos(),
+                        thread::current().id(),
+                        local_last_known_problem_index
+                    );
+                }
+                return true;
+            }
+
+            let mut callback_state = shared_state.callback.lock().unwrap();
+            //Another thread may have won while this one waited for the lock
+            if shared_state.is_stale(local_last_known_problem_index) {
+                return true;
+            }
+            //Another thread already handed this one to the callback, which recorded it
+            if callback_state.recorded.contains(solved_sudoku) {
+                return false;
+            }
+            shared_state.solutions.fetch_add(1, Ordering::Relaxed);
+            let Some(callback) = &mut callback_state.solution_callback else {
+                return true;
+            };
+            //Catching the panic here keeps it from poisoning the lock for the other threads. The
+            //callback is dropped below and never called again, so it cannot be seen half updated.
+            let action =
+                match panic::catch_unwind(AssertUnwindSafe(|| callback(solved_sudoku).into())) {
+                    Ok(action) => action,
+                    Err(payload) => {
+                        callback_state.callback_panic =
+                            Some(CallbackPanic::new(payload, PanicSource::SolutionCallback));
+                        SolutionAction::Abort
+                    }
+                };
+            let stop = callback_state.found.keep(solved_sudoku, action);
+            if action == SolutionAction::Record {
+                callback_state.recorded.insert(solved_sudoku.clone());
+            }
+            if stop {
+                //The callback accepted a solution or aborted the current problem, which was unsolved
+                //Mark it solved, which cancels the other threads
+                if MULTITHREADING_DEBUG {
+                    thread_println!(
+                        "{:?}: Thread {:?} SUCCESS has solved problem {}",
+                        PROGRAM_START_TIME.elapsed().as_nanos(),
+                        thread::current().id(),
+                        local_last_known_problem_index
+                    );
+                }
+                callback_state.solution_callback = None;
+                let mut shared_context = shared_state.context.lock().unwrap();
+                if let Some(ordering) = local_ordering.filter(|_| action == SolutionAction::Accept)
+                {
+                    shared_context.ordering_stats[ordering].wins += 1;
+                }
+                shared_state.solved.store(true, Ordering::Release);
+            } else if MULTITHREADING_DEBUG {
+                thread_println!(
+                    "{:?}: Thread {:?} CONTINUING,[via on_solved] for problem {}",
+                    PROGRAM_START_TIME.elapsed().as_nanos(),
+                    thread::current().id(),
+                    local_last_known_problem_index
+                );
+            }
+            stop
+        };
+
+        let check_interval = shared_state.check_interval.load(Ordering::Relaxed);
+        let is_stale = || {
+            let nodes = shared_state
+                .nodes
+                .fetch_add(check_interval as u64, Ordering::Relaxed)
+                + check_interval as u64;
+            if local_limits.is_exceeded(nodes) {
+                shared_state
+                    .stop_early(local_last_known_problem_index, &shared_state.limit_reached);
+            } else if local_limits.is_cancelled() {
+                shared_state.stop_early(local_last_known_problem_index, &shared_state.cancelled);
+            }
+            let should_stop = shared_state.is_stale(local_last_known_problem_index);
+            if MULTITHREADING_DEBUG {
+                if should_stop {
+                    thread_println!(
+                        "{:?}: Thread {:?} CANCELLING problem {} [via is_cancelled]",
+                        PROGRAM_START_TIME.elapsed().as_nanos(),
+                        thread::current().id(),
+                        local_last_known_problem_index
+                    );
+                } else {
+                    thread_println!(
+                        "{:?}: Thread {:?} CONTINUING,[via is_cancelled] for problem {}",
+                        PROGRAM_START_TIME.elapsed().as_nanos(),
+                        thread::current().id(),
+                        local_last_known_problem_index
+                    );
+                }
+            }
+            should_stop
+        };
+
+        //Caught on its own, so the backend it is called from is not blamed for it
+        let report_progress = |progress: &SolveProgress| {
+            if let Some(callback) = &local_progress {
+                let reported = panic::catch_unwind(AssertUnwindSafe(|| {
+                    callback(&SolveProgress {
+                        nodes: shared_state.nodes.load(Ordering::Relaxed),
+                        ..*progress
+                    })
+                }));
+                if let Err(payload) = reported {
+                    shared_state.fail(
+                        local_last_known_problem_index,
+                        CallbackPanic::new(payload, PanicSource::ProgressCallback),
+                    );
+                }
+            }
+        };
+        //A panicking backend fails the problem, but the thread stays for the next one. The callback
+        //panics are caught in `on_solution` and `report_progress`, so no lock is held here.
+        let run_backend = |backend: &dyn SolverBackend| {
+            let searched = panic::catch_unwind(AssertUnwindSafe(|| {
+                backend.solve_with_progress(
+                    &mut local_last_known_problem.clone(),
+                    &local_allowed_candidates,
+                    &on_solution,
+                    &is_stale,
+                    check_interval,
+                    &report_progress,
+                )
+            }));
+            if let Err(payload) = searched {
+                shared_state.fail(
+                    local_last_known_problem_index,
+                    CallbackPanic::new(payload, PanicSource::Backend),
+                );
+            }
+        };
+        let mut exhaustive = true;
+        if let Some(backend) = &local_backend {
+            run_backend(backend.as_ref());
+            exhaustive = backend.is_exhaustive();
+            //`solve` returns once the calling thread is done here, so if its backend gave up it
+            //searches on by itself instead of leaving the problem to the threads it then stops
+            if !exhaustive && !STAY_ALIVE && !shared_state.is_stale(local_last_known_problem_index)
+            {
+                if MULTITHREADING_DEBUG {
+                    thread_println!(
+                        "{:?}: Thread {:?} GAVE UP on problem {} with its backend",
+                        PROGRAM_START_TIME.elapsed().as_nanos(),
+                        thread::current().id(),
+                        local_last_known_problem_index
+                    );
+                }
+                run_backend(&DynamicBacktracking);
+                exhaustive = true;
+            }
+        } else {
+            for restart in 0.. {
+                let cells = match local_ordering {
+                    Some(ordering) => CELL_ORDERINGS[ordering].cells,
+                    None => random_cell_order(
+                        local_random.seed
+                            ^ (slot as u64) << 40
+                            ^ (local_last_known_problem_index as u64) << 20
+                            ^ restart,
+                    ),
+                };
+                //Fixed orderings are never restarted, they would only repeat the same search
+                let budget = local_random
+                    .restart_base
+                    .filter(|_| local_ordering.is_none())
+                    .map(|base| base * luby(restart + 1));
+                let nodes = core::cell::Cell::new(0_u64);
+                let budget_spent = core::cell::Cell::new(false);
+                solve_single_thread_with_progress::<false, _, _>(
+                    &mut local_last_known_problem.clone(),
+                    &local_allowed_candidates,
+                    &on_solution,
+                    || {
+                        if budget.is_some_and(|budget| {
+                            nodes.set(nodes.get() + check_interval as u64);
+                            nodes.get() >= budget
+                        }) {
+                            budget_spent.set(true);
+                            return true;
+                        }
+                        is_stale()
+                    },
+                    |x| cells[x],
+                    check_interval,
+                    report_progress,
+                );
+
+                if !budget_spent.get() || shared_state.is_stale(local_last_known_problem_index) {
+                    break;
+                }
+                if MULTITHREADING_DEBUG {
+                    thread_println!(
+                        "{:?}: Thread {:?} RESTARTING problem {} after {} nodes",
+                        PROGRAM_START_TIME.elapsed().as_nanos(),
+                        thread::current().id(),
+                        local_last_known_problem_index,
+                        nodes.get()
+                    );
+                }
+            }
+        }
+
+        //A complete search that ran out without being cancelled has offered every solution to the
+        //callback, so the other threads cannot find one it accepts either. Cell order searches only
+        //get here unfinished when they are stale, restarts go on until one runs out.
+        if exhaustive && !shared_state.is_stale(local_last_known_problem_index) {
+            let _shared_context = shared_state.context.lock().unwrap();
+            if !shared_state.is_stale(local_last_known_problem_index) {
+                if MULTITHREADING_DEBUG {
+                    thread_println!(
+                        "{:?}: Thread {:?} EXHAUSTED problem {}",
+                        PROGRAM_START_TIME.elapsed().as_nanos(),
+                        thread::current().id(),
+                        local_last_known_problem_index
+                    );
+                }
+                shared_state.solved.store(true, Ordering::Release);
+            }
+        }
+
+        if !STAY_ALIVE {
+            if MULTITHREADING_DEBUG {
+                thread_println!(
+                    "{:?}: Thread {:?} has !STAY_ALIVE, yielding control to caller.",
+                    PROGRAM_START_TIME.elapsed().as_nanos(),
+                    thread::current().id()
+                );
+            }
+            break;
+        }
+    }
+}
+
+pub fn with_multithreaded_solver<
+    T,
+    SOLFN: FnMut(&Sudoku) -> R + std::marker::Send,
+    R: Into<SolutionAction>,
+>(
+    solving_callback: impl Fn(&mut Solver<SOLFN>) -> T,
+) -> T {
+    let mut ret_val: Option<T> = None;
+
+    let helper_threads = default_thread_count() - 1;
+    let shared_state = SharedState::new(helper_threads);
+    let mut solver = Solver {
+        shared_state: &shared_state,
+    };
+
+    thread::scope(|scope| {
+        for slot in 1..=helper_threads {
+            let shared_state_ref = &shared_state;
+            scope.spawn(move || {
+                if MULTITHREADING_DEBUG {
+                    thread_println!(
+                        "{:?}: Spawned helper thread {:?}",
+                        PROGRAM_START_TIME.elapsed().as_nanos(),
+                        thread::current().id()
+                    );
+                }
+
+                multithreaded_helper::<true, _, _>(shared_state_ref, slot);
+            });
+        }
+
+        ret_val = Some(solving_callback(&mut solver));
+
+        // Shut down the threads
+        shared_state.shut_down();
+    });
+    ret_val.unwrap()
+}
+
+/// How a submitted job ended
+#[derive(Clone)]
+pub enum JobOutcome {
+    /// The solution the callback accepted
+    Solved(Sudoku),
+    /// The callback accepted none of the solutions or aborted, or there was none
+    NoSolution,
+    /// The limits the job was submitted with ran out
+    LimitReached(SolveStats),
+    /// Cancelled through its handle or the `CancellationToken` of its limits, or the pool was
+    /// dropped before the job finished
+    Cancelled,
+    /// The callback or the backend panicked, which stopped the job but not the thread running it
+    Panicked(CallbackPanic),
+}
+
+struct JobState {
+    id: u64,
+    cancelled: AtomicBool,
+    //None until the job finished
+    outcome: Mutex<Option<JobOutcome>>,
+    finished: Condvar,
+    recorded: Mutex<Vec<Sudoku>>,
+    //None until the job searched for a check interval
+    progress: Mutex<Option<SolveProgress>>,
+}
+
+impl JobState {
+    fn finish(&self, outcome: JobOutcome) {
+        *self.outcome.lock().unwrap() = Some(outcome);
+        self.finished.notify_all();
+    }
+}
+
+struct Job {
+    sudoku: Sudoku,
+    allowed_candidates: [CandidateSet; 81],
+    callback: PoolCallback,
+    limits: SolveLimits,
+    state: Arc<JobState>,
+}
+
+impl Job {
+    //Solves the job on the calling thread alone, `shut_down` cancels it along with all other jobs
+    fn run(
+        mut self,
+        backend: &dyn SolverBackend,
+        check_interval: u32,
+        shut_down: impl Fn() -> bool,
+    ) {
+        let is_cancelled = || self.state.cancelled.load(Ordering::Acquire) || shut_down();
+        let callback = core::cell::RefCell::new(self.callback);
+        let found = core::cell::RefCell::new(Solutions::default());
+        let callback_panic = core::cell::Cell::new(None);
+        let mut outcome = None;
+        if !is_cancelled() && !self.limits.is_cancelled() {
+            let on_solution = |s: &Sudoku| match panic::catch_unwind(AssertUnwindSafe(|| {
+                (callback.borrow_mut())(s)
+            })) {
+                Ok(action) => {
+                    //`recorded` is read while the job runs, so it is kept next to `found`
+                    if action == SolutionAction::Record {
+                        self.state.recorded.lock().unwrap().push(s.clone());
+                    }
+                    found.borrow_mut().keep(s, action)
+                }
+                Err(payload) => {
+                    callback_panic.set(Some(CallbackPanic::new(
+                        payload,
+                        PanicSource::SolutionCallback,
+                    )));
+                    true
+                }
+            };
+            let searched = panic::catch_unwind(AssertUnwindSafe(|| {
+                backend.solve_with_limits(
+                    &mut self.sudoku,
+                    &self.allowed_candidates,
+                    &on_solution,
+                    &is_cancelled,
+                    check_interval,
+                    &self.limits,
+                    &|progress| *self.state.progress.lock().unwrap() = Some(*progress),
+                )
+            }));
+            match searched {
+                Ok(searched) => outcome = Some(searched),
+                //The thread running the job keeps going, like it does for callback panics
+                Err(payload) => {
+                    callback_panic.set(Some(CallbackPanic::new(payload, PanicSource::Backend)))
+                }
+            }
+        }
+        if let Some(callback_panic) = callback_panic.take() {
+            self.state.finish(JobOutcome::Panicked(callback_panic));
+            return;
+        }
+        let outcome = match (found.into_inner().accepted, outcome) {
+            (Some(solution), _) => JobOutcome::Solved(solution),
+            (None, Some(SolveOutcome::LimitReached(stats))) => JobOutcome::LimitReached(stats),
+            (None, Some(SolveOutcome::Finished(_))) => JobOutcome::NoSolution,
+            (None, Some(SolveOutcome::Cancelled(_)) | None) => JobOutcome::Cancelled,
+        };
+        self.state.finish(outcome);
+    }
+}
+
+/// Refers to a job submitted with `SolverPool::submit`
+pub struct JobHandle {
+    state: Arc<JobState>,
+    shared_state: Arc<SharedState<PoolCallback>>,
+}
+
+impl JobHandle {
+    /// Unique among the jobs of one pool
+    pub fn id(&self) -> u64 {
+        self.state.id
+    }
+
+    /// Stops the job at its next cancellation check, or before it starts if it is still queued
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Release);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.outcome.lock().unwrap().is_some()
+    }
+
+    /// The outcome if the job finished, without waiting for it
+    pub fn outcome(&self) -> Option<JobOutcome> {
+        self.state.outcome.lock().unwrap().clone()
+    }
+
+    /// The solutions the callback recorded so far, in the order they were handed to it
+    pub fn recorded(&self) -> Vec<Sudoku> {
+        self.state.recorded.lock().unwrap().clone()
+    }
+
+    /// The last progress the job reported, `None` until it searched for a check interval
+    pub fn progress(&self) -> Option<SolveProgress> {
+        *self.state.progress.lock().unwrap()
+    }
+
+    /// Blocks until the job finished. A job no helper thread has picked up yet is run on the
+    /// calling thread instead, so this also works for pools without helper threads.
+    pub fn wait(&self) -> JobOutcome {
+        let queued = {
+            let mut shared_context = self.shared_state.context.lock().unwrap();
+            let position = shared_context
+                .jobs
+                .iter()
+                .position(|job| job.state.id == self.state.id);
+            let backend = shared_context.portfolio.first().cloned();
+            position.and_then(|k| Some((shared_context.jobs.remove(k)?, backend)))
+        };
+        if let Some((job, backend)) = queued {
+            job.run(
+                backend.as_deref().unwrap_or(&DynamicBacktracking),
+                self.shared_state.check_interval.load(Ordering::Relaxed),
+                || self.shared_state.generation.load(Ordering::Acquire) == -1,
+            );
+        }
+        let outcome = self.state.outcome.lock().unwrap();
+        self.state
+            .finished
+            .wait_while(outcome, |outcome| outcome.is_none())
+            .unwrap()
+            .clone()
+            .unwrap()
+    }
+}
+
+// Callbacks borrowing from the caller's stack are stored with their lifetime erased, see
+// `SolverPool::solve_with_candidates`
+type PoolCallback = Box<dyn FnMut(&Sudoku) -> SolutionAction + std::marker::Send>;
+
+/// Same search as `with_multithreaded_solver`, but the helper threads live as long as the pool
+/// instead of one closure. The pool can be shared between threads, e.g. in an `Arc`. Solves from
+/// different threads queue up and run one at a time. Dropping the pool shuts the helpers down and
+/// waits for them.
+pub struct SolverPool {
+    shared_state: Arc<SharedState<PoolCallback>>,
+    helpers: Vec<thread::JoinHandle<()>>,
+    //Held for the whole solve, only one problem can be live at a time
+    solving: Mutex<()>,
+}
+
+/// Configures a `SolverPool`, see `SolverPool::builder`
+pub struct SolverPoolBuilder {
+    threads: Option<usize>,
+    thread_name: String,
+    cpus: Option<Vec<usize>>,
+}
+
+impl SolverPoolBuilder {
+    /// Threads searching every problem, counting the thread that calls `solve`. Defaults to the
+    /// available parallelism, or 1 if that cannot be determined.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads.max(1));
+        self
+    }
+
+    /// Helper threads are named `{name}-1`, `{name}-2` and so on. Defaults to "sudoku-solver".
+    pub fn thread_name(mut self, name: impl Into<String>) -> Self {
+        self.thread_name = name.into();
+        self
+    }
+
+    /// Pins helper thread `k` to CPU `cpus[(k - 1) % cpus.len()]`. The thread calling `solve` is
+    /// left alone. Only supported on Linux.
+    pub fn pin_to_cpus(mut self, cpus: Vec<usize>) -> Self {
+        self.cpus = Some(cpus);
+        self
+    }
+
+    /// Spawns the helper threads. Fails if a thread cannot be spawned or pinned, in which case the
+    /// threads spawned so far are shut down again.
+    pub fn build(self) -> io::Result<SolverPool> {
+        if self.cpus.as_ref().is_some_and(|cpus| cpus.is_empty()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no CPUs to pin the threads to",
+            ));
+        }
+        let helper_threads = self.threads.unwrap_or_else(default_thread_count) - 1;
+        let mut pool = SolverPool {
+            shared_state: Arc::new(SharedState::new(helper_threads)),
+            helpers: Vec::with_capacity(helper_threads),
+            solving: Mutex::new(()),
+        };
+        //Every helper reports whether it could be pinned before it starts waiting for problems
+        let (started, started_receiver) = mpsc::channel();
+        for slot in 1..=helper_threads {
+            let shared_state = pool.shared_state.clone();
+            let cpu = self.cpus.as_ref().map(|cpus| cpus[(slot - 1) % cpus.len()]);
+            let started = started.clone();
+            let helper = thread::Builder::new()
+                .name(format!("{}-{}", self.thread_name, slot))
+                .spawn(move || {
+                    let pinned = cpu.map_or(Ok(()), pin_current_thread);
+                    let is_pinned = pinned.is_ok();
+                    let _ = started.send(pinned);
+                    if !is_pinned {
+                        return;
+                    }
+                    if MULTITHREADING_DEBUG {
+                        thread_println!(
+                            "{:?}: Spawned pool helper thread {:?}",
+                            PROGRAM_START_TIME.elapsed().as_nanos(),
+                            thread::current().id()
+                        );
+                    }
+                    multithreaded_helper::<true, _, _>(&shared_state, slot);
+                })?;
+            pool.helpers.push(helper);
+        }
+        for _ in 0..helper_threads {
+            started_receiver.recv().unwrap()?;
+        }
+        Ok(pool)
+    }
+}
+
+fn default_thread_count() -> usize {
+    thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+#[cfg(target_os = "linux")]
+fn pin_current_thread(cpu: usize) -> io::Result<()> {
+    unsafe extern "C" {
+        fn sched_setaffinity(pid: i32, cpusetsize: usize, mask: *const u64) -> i32;
+    }
+    //Same layout as glibc's cpu_set_t
+    let mut mask = [0_u64; 16];
+    if cpu >= 64 * mask.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("CPU {} is out of range", cpu),
+        ));
+    }
+    mask[cpu / 64] |= 1 << (cpu % 64);
+    //SAFETY: pid 0 is the calling thread and the mask outlives the call
+    if unsafe { sched_setaffinity(0, size_of_val(&mask), mask.as_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_current_thread(_cpu: usize) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "pinning threads is only supported on Linux",
+    ))
+}
+
+impl SolverPool {
+    /// A pool with the default configuration, panics if the helper threads cannot be spawned
+    pub fn new() -> Self {
+        Self::builder()
+            .build()
+            .expect("failed to spawn solver threads")
+    }
+
+    pub fn builder() -> SolverPoolBuilder {
+        SolverPoolBuilder {
+            threads: None,
+            thread_name: "sudoku-solver".to_string(),
+            cpus: None,
+        }
+    }
+
+    /// See `Solver::solve`. Helper threads busy with a job from `submit` only join the search
+    /// once their job is done, so a long job leaves this solve with fewer threads. `callback` may
+    /// submit jobs and wait for them, but a `solve` on the same pool from within it never returns,
+    /// as solves run one at a time.
+    pub fn solve<R: Into<SolutionAction>>(
+        &self,
+        sudoku: &mut Sudoku,
+        callback: impl FnMut(&Sudoku) -> R + Send,
+    ) -> Result<Solutions, CallbackPanic> {
+        self.solve_with_candidates(sudoku, &ALL_CANDIDATES, callback)
+    }
+
+    /// Like `solve`, but each empty cell may only take the digits in its `allowed_candidates` mask
+    pub fn solve_with_candidates<R: Into<SolutionAction>>(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: impl FnMut(&Sudoku) -> R + Send,
+    ) -> Result<Solutions, CallbackPanic> {
+        self.solve_with_limits(
+            sudoku,
+            allowed_candidates,
+            callback,
+            &SolveLimits::default(),
+        )
+        .map(|(_, solutions)| solutions)
+    }
+
+    /// Like `solve_with_candidates`, but gives up once `limits` run out. Like with `solve`,
+    /// `callback` must not solve on the same pool.
+    pub fn solve_with_limits<'a, R: Into<SolutionAction>>(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        mut callback: impl FnMut(&Sudoku) -> R + Send + 'a,
+        limits: &SolveLimits,
+    ) -> Result<(SolveOutcome, Solutions), CallbackPanic> {
+        //Only poisoned by a progress callback panicking on the calling thread, which leaves
+        //nothing to clean up
+        let _solving = self.solving.lock().unwrap_or_else(PoisonError::into_inner);
+        let callback: Box<dyn FnMut(&Sudoku) -> SolutionAction + Send + 'a> =
+            Box::new(move |s| callback(s).into());
+        //SAFETY: `SharedState::solve` takes the callback back out under the context lock before it
+        //returns or unwinds, and helper threads only call it while holding that lock, so it is
+        //never called or dropped after 'a ends
+        let callback: PoolCallback = unsafe { core::mem::transmute(callback) };
+        self.shared_state
+            .solve(sudoku, allowed_candidates, callback, limits)
+    }
+
+    /// Queues `sudoku` and returns right away. Idle helper threads pick up queued jobs in order and
+    /// solve each one on their own, with their backend from the portfolio or `DynamicBacktracking`.
+    /// A job is never split between threads, so a hard one takes as long as a single-threaded
+    /// solve. A problem passed to `solve` goes before queued jobs, but does not interrupt jobs
+    /// already running: their helper threads stay unavailable to it until they finish.
+    pub fn submit<R: Into<SolutionAction>>(
+        &self,
+        sudoku: Sudoku,
+        callback: impl FnMut(&Sudoku) -> R + Send + 'static,
+    ) -> JobHandle {
+        self.submit_with_candidates(sudoku, &ALL_CANDIDATES, callback)
+    }
+
+    /// Like `submit`, but each empty cell may only take the digits in its `allowed_candidates` mask
+    pub fn submit_with_candidates<R: Into<SolutionAction>>(
+        &self,
+        sudoku: Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: impl FnMut(&Sudoku) -> R + Send + 'static,
+    ) -> JobHandle {
+        self.submit_with_limits(
+            sudoku,
+            allowed_candidates,
+            callback,
+            &SolveLimits::default(),
+        )
+    }
+
+    /// Like `submit_with_candidates`, but the job gives up once `limits` run out. Time spent in the
+    /// queue counts towards the deadline.
+    pub fn submit_with_limits<R: Into<SolutionAction>>(
+        &self,
+        sudoku: Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        mut callback: impl FnMut(&Sudoku) -> R + Send + 'static,
+        limits: &SolveLimits,
+    ) -> JobHandle {
+        let mut shared_context = self.shared_state.context.lock().unwrap();
+        let state = Arc::new(JobState {
+            id: shared_context.next_job_id,
+            cancelled: AtomicBool::new(false),
+            outcome: Mutex::new(None),
+            finished: Condvar::new(),
+            recorded: Mutex::new(Vec::new()),
+            progress: Mutex::new(None),
+        });
+        shared_context.next_job_id += 1;
+        shared_context.jobs.push_back(Job {
+            sudoku,
+            allowed_candidates: *allowed_candidates,
+            callback: Box::new(move |s| callback(s).into()),
+            limits: limits.clone(),
+            state: state.clone(),
+        });
+        self.shared_state.new_problem.notify_one();
+        JobHandle {
+            state,
+            shared_state: self.shared_state.clone(),
+        }
+    }
+
+    /// Runs and wins of every ordering over the problems solved so far
+    pub fn ordering_stats(&self) -> [OrderingStats; 9] {
+        self.shared_state.ordering_stats()
+    }
+
+    /// See `Solver::set_random_orderings`
+    pub fn set_random_orderings(&self, random_orderings: Option<RandomOrderings>) {
+        self.shared_state.set_random_orderings(random_orderings);
+    }
+
+    /// See `Solver::set_portfolio`
+    pub fn set_portfolio(&self, portfolio: Vec<Arc<dyn SolverBackend>>) {
+        self.shared_state.set_portfolio(portfolio);
+    }
+
+    /// See `Solver::set_adaptive_orderings`
+    pub fn set_adaptive_orderings(&self, adaptive: bool) {
+        self.shared_state.set_adaptive_orderings(adaptive);
+    }
+
+    /// See `Solver::set_progress_callback`
+    pub fn set_progress_callback(&self, callback: Option<ProgressCallback>) {
+        self.shared_state.set_progress_callback(callback);
+    }
+
+    /// See `Solver::set_check_interval`
+    pub fn set_check_interval(&self, nodes: u32) {
+        self.shared_state.set_check_interval(nodes);
+    }
+}
+
+impl Default for SolverPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SolverPool {
+    fn drop(&mut self) {
+        self.shared_state.shut_down();
+        for helper in self.helpers.drain(..) {
+            let _ = helper.join();
+        }
+        //Jobs nobody picked up will never run
+        let jobs = core::mem::take(
+            &mut self
+                .shared_state
+                .context
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .jobs,
+        );
+        for job in jobs {
+            job.state.finish(JobOutcome::Cancelled);
+        }
+    }
+}
+
+pub struct BatchResult {
+    /// One entry per input puzzle in input order, `None` when the puzzle has no solution
+    pub solutions: Vec<Option<Sudoku>>,
+    pub elapsed: Duration,
+}
+
+impl BatchResult {
+    pub fn puzzles_per_second(&self) -> f64 {
+        self.solutions.len() as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+/// Solves many puzzles at once, each worker thread takes the next unsolved puzzle and solves it on
+/// its own. Better throughput than `with_multithreaded_solver` when most puzzles are easy.
+pub fn solve_batch<I>(puzzles: I, backend: &dyn SolverBackend, threads: usize) -> BatchResult
+where
+    I: IntoIterator<Item = Sudoku>,
+    I::IntoIter: Send,
+{
+    solve_batch_with_limits(puzzles, backend, threads, &SolveLimits::default()).1
+}
+
+/// Like `solve_batch`, but gives up once `limits` run out or their token is cancelled. The limits
+/// cover the whole batch, the puzzles that were not solved by then are `None` as well. The solutions of the stats are the
+/// puzzles solved.
+pub fn solve_batch_with_limits<I>(
+    puzzles: I,
+    backend: &dyn SolverBackend,
+    threads: usize,
+    limits: &SolveLimits,
+) -> (SolveOutcome, BatchResult)
+where
+    I: IntoIterator<Item = Sudoku>,
+    I::IntoIter: Send,
+{
+    let batch_start = Instant::now();
+    let puzzles = Mutex::new(puzzles.into_iter().enumerate());
+    let mut solutions = Vec::new();
+    let nodes = AtomicU64::new(0);
+    let limit_reached = AtomicBool::new(false);
+    let cancelled = AtomicBool::new(false);
+    //Easy puzzles may be solved before the first check, so the limits are checked between puzzles
+    //as well
+    let stop = |added_nodes: u64| {
+        let nodes = nodes.fetch_add(added_nodes, Ordering::Relaxed) + added_nodes;
+        if limits.is_exceeded(nodes) {
+            limit_reached.store(true, Ordering::Relaxed);
+        } else if limits.is_cancelled() {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+        limit_reached.load(Ordering::Relaxed) || cancelled.load(Ordering::Relaxed)
+    };
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut solved = Vec::new();
+                    loop {
+                        let next = puzzles.lock().unwrap().next();
+                        let Some((k, mut sudoku)) = next else {
+                            break;
+                        };
+                        //The remaining puzzles still get their entry
+                        if stop(0) {
+                            solved.push((k, None));
+                            continue;
+                        }
+                        let solution = core::cell::Cell::new(None);
+                        backend.solve(
+                            &mut sudoku,
+                            &ALL_CANDIDATES,
+                            &|s| {
+                                solution.set(Some(s.clone()));
+                                true
+                            },
+                            &|| stop(DEFAULT_CHECK_INTERVAL as u64),
+                            DEFAULT_CHECK_INTERVAL,
+                        );
+                        solved.push((k, solution.take()));
+                    }
+                    solved
+                })
+            })
+            .collect();
+        for worker in workers {
+            solutions.extend(worker.join().unwrap());
+        }
+    });
+
+    solutions.sort_unstable_by_key(|(k, _)| *k);
+    let stats = SolveStats {
+        nodes: nodes.load(Ordering::Relaxed),
+        solutions: solutions.iter().filter(|(_, s)| s.is_some()).count() as u64,
+        elapsed: batch_start.elapsed(),
+    };
+    let outcome = if limit_reached.load(Ordering::Relaxed) {
+        SolveOutcome::LimitReached(stats)
+    } else if cancelled.load(Ordering::Relaxed) {
+        SolveOutcome::Cancelled(stats)
+    } else {
+        SolveOutcome::Finished(stats)
+    };
+    let result = BatchResult {
+        solutions: solutions.into_iter().map(|(_, s)| s).collect(),
+        elapsed: batch_start.elapsed(),
+    };
+    (outcome, result)
+}
+
+// Cells filled by singles propagation, in the order they were filled so they can be undone
+struct SinglesTrail {
+    cells: [u8; 81],
+    len: usize,
+}
+
+impl SinglesTrail {
+    fn new() -> Self {
+        SinglesTrail {
+            cells: [0; _],
+            len: 0,
+        }
+    }
+
+    fn place(&mut self, sudoku: &mut impl PuzzleCore, index: u8, digit: u8) {
+        sudoku.set(index, digit);
+        self.cells[self.len] = index;
+        self.len += 1;
+    }
+
+    fn undo(&mut self, sudoku: &mut impl PuzzleCore, len: usize) {
+        while self.len > len {
+            self.len -= 1;
+            sudoku.set(self.cells[self.len], 0);
+        }
+    }
+
+    // Fills naked and hidden singles until there are none left.
+    // Returns false when some cell has no candidates or some digit has no place left in a unit.
+    fn propagate(
+        &mut self,
+        sudoku: &mut impl PuzzleCore,
+        allowed_candidates: &[CandidateSet; 81],
+    ) -> bool {
+        loop {
+            let len = self.len;
+            for index in 0..81 {
+                if sudoku.is_missing(index) {
+                    let candidates =
+                        sudoku.get_candidates(index) & allowed_candidates[index as usize];
+                    match candidates.len() {
+                        0 => return false,
+                        1 => self.place(sudoku, index, candidates.0.trailing_zeros() as u8 + 1),
+                        _ => {}
+                    }
+                }
+            }
+            for unit in pencil::UNITS {
+                let mut placed = 0;
+                let mut once = 0;
+                let mut twice = 0;
+                for index in unit {
+                    match sudoku.get(index) {
+                        0 => {
+                            let candidates = (sudoku.get_candidates(index)
+                                & allowed_candidates[index as usize])
+                                .0;
+                            twice |= once & candidates;
+                            once |= candidates;
+                        }
+                        val => placed |= 1 << (val - 1),
+                    }
+                }
+                if once | placed != 0b111111111 {
+                    return false;
+                }
+                let hidden = once & !twice;
+                //The cell has to be picked by the same masked candidates the digits were counted by
+                let allowed = |index: u8| {
+                    (sudoku.get_candidates(index) & allowed_candidates[index as usize]).0 & hidden
+                };
+                if hidden != 0
+                    && let Some(index) = unit
+                        .into_iter()
+                        .find(|index| sudoku.is_missing(*index) && allowed(*index) != 0)
+                {
+                    let digit = allowed(index).trailing_zeros() as u8 + 1;
+                    self.place(sudoku, index, digit);
+                }
+            }
+            if self.len == len {
+                return true;
+            }
+        }
+    }
+}
+
+/// Search nodes visited between two calls of `is_cancelled`
+pub const DEFAULT_CHECK_INTERVAL: u32 = 100000;
+
+/// Stops the solves it was passed to from any thread, e.g. when a user hits "stop". Clones share
+/// the same state, so one clone can be handed to the solve and another kept for cancelling it.
+#[derive(Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Limits after which a search gives up. All of them are checked together with cancellation, so a
+/// search may run up to one check interval past them.
+#[derive(Clone, Default)]
+pub struct SolveLimits {
+    pub deadline: Option<Instant>,
+    /// Search nodes over all threads
+    pub max_nodes: Option<u64>,
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl SolveLimits {
+    pub fn with_timeout(timeout: Duration) -> Self {
+        SolveLimits {
+            deadline: Some(Instant::now() + timeout),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_cancellation(token: CancellationToken) -> Self {
+        SolveLimits {
+            cancellation: Some(token),
+            ..Self::default()
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    fn is_exceeded(&self, nodes: u64) -> bool {
+        self.max_nodes.is_some_and(|max_nodes| nodes >= max_nodes)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// What a search got through before it returned
+#[derive(Clone, Copy, Default, Debug)]
+pub struct SolveStats {
+    /// Search nodes, counted in steps of the check interval
+    pub nodes: u64,
+    /// Solutions handed to the callback
+    pub solutions: u64,
+    pub elapsed: Duration,
+}
+
+/// Snapshot of a running search
+#[derive(Clone, Copy, Debug)]
+pub struct SolveProgress {
+    /// Search nodes so far, counted in steps of the check interval
+    pub nodes: u64,
+    /// Cells the search has guessed a digit for
+    pub depth: usize,
+    /// Share of the search tree searched so far, between 0 and 1. Stays 0 for backends that
+    /// cannot estimate it.
+    pub estimated_fraction: f64,
+}
+
+impl SolveProgress {
+    // Knuth-style estimate from the stack frames, given as (finished digits, digits) from the
+    // bottom up. All subtrees of a frame are taken to be the same size, so every finished digit of
+    // the frame at depth d covers 1 / (digits_1 * ... * digits_d) of the tree.
+    fn estimate(nodes: u64, frames: impl Iterator<Item = (u32, u32)>) -> Self {
+        let mut depth = 0;
+        let mut estimated_fraction = 0.0;
+        let mut subtree = 1.0;
+        for (finished, digits) in frames {
+            depth += 1;
+            subtree /= digits as f64;
+            estimated_fraction += finished as f64 * subtree;
+        }
+        SolveProgress {
+            nodes,
+            depth,
+            estimated_fraction,
+        }
+    }
+}
+
+/// Receives the progress of multithreaded solves, see `Solver::set_progress_callback`
+pub type ProgressCallback = Arc<dyn Fn(&SolveProgress) + Send + Sync>;
+
+/// How a search with `SolveLimits` ended
+#[derive(Clone, Copy, Debug)]
+pub enum SolveOutcome {
+    /// The callback accepted a solution, or every solution was offered to it
+    Finished(SolveStats),
+    /// A limit ran out first
+    LimitReached(SolveStats),
+    /// Cancelled through the `CancellationToken` of the limits or the `is_cancelled` callback
+    Cancelled(SolveStats),
+}
+
+impl SolveOutcome {
+    pub fn stats(&self) -> SolveStats {
+        match self {
+            SolveOutcome::Finished(stats)
+            | SolveOutcome::LimitReached(stats)
+            | SolveOutcome::Cancelled(stats) => *stats,
+        }
+    }
+}
+
+/// What a search does with a solution after handing it to the callback. Callbacks returning `bool`
+/// keep working, true accepts and false rejects.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SolutionAction {
+    /// Keep the solution as the accepted one and stop
+    Accept,
+    /// Drop the solution and go on searching
+    Reject,
+    /// Add the solution to the recorded ones and go on searching
+    Record,
+    /// Stop without accepting a solution
+    Abort,
+}
+
+impl From<bool> for SolutionAction {
+    fn from(accepted: bool) -> Self {
+        if accepted {
+            SolutionAction::Accept
+        } else {
+            SolutionAction::Reject
+        }
+    }
+}
+
+/// The solutions a search kept, depending on what its callback returned for them
+#[derive(Clone)]
+pub struct Solutions<P = Sudoku> {
+    /// The solution the callback accepted
+    pub accepted: Option<P>,
+    /// The solutions the callback recorded, in the order they were handed to it
+    pub recorded: Vec<P>,
+    /// Whether the callback aborted the search
+    pub aborted: bool,
+}
+
+impl<P> Default for Solutions<P> {
+    fn default() -> Self {
+        Solutions {
+            accepted: None,
+            recorded: Vec::new(),
+            aborted: false,
+        }
+    }
+}
+
+impl<P: Clone> Solutions<P> {
+    //Keeps `solution` the way `action` says, true if the search has to stop
+    fn keep(&mut self, solution: &P, action: SolutionAction) -> bool {
+        match action {
+            SolutionAction::Accept => {
+                self.accepted = Some(solution.clone());
+                true
+            }
+            SolutionAction::Reject => false,
+            SolutionAction::Record => {
+                self.recorded.push(solution.clone());
+                false
+            }
+            SolutionAction::Abort => {
+                self.aborted = true;
+                true
+            }
+        }
+    }
+}
+
+/// What panicked during a search
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanicSource {
+    /// The callback handed the solutions
+    SolutionCallback,
+    /// The callback set with `set_progress_callback`
+    ProgressCallback,
+    /// The backend searching the problem
+    Backend,
+}
+
+/// A callback or a solver backend panicked. The search it was called from stopped, the solver and
+/// its threads can be used for the next problem.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallbackPanic {
+    /// The panic message, or a placeholder if the panic did not carry a string
+    pub message: String,
+    /// Whether a callback or the backend panicked
+    pub source: PanicSource,
+}
+
+impl CallbackPanic {
+    fn new(payload: Box<dyn std::any::Any + Send>, source: PanicSource) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "Box<dyn Any>".to_string(),
+            },
+        };
+        CallbackPanic { message, source }
+    }
+}
+
+impl core::fmt::Display for CallbackPanic {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let source = match self.source {
+            PanicSource::SolutionCallback => "solution callback",
+            PanicSource::ProgressCallback => "progress callback",
+            PanicSource::Backend => "solver backend",
+        };
+        write!(f, "{source} panicked: {}", self.message)
+    }
+}
+
+impl std::error::Error for CallbackPanic {}
+
+/// `allowed_candidates` is intersected with the candidates of every empty cell before it is tried.
+/// With `PROPAGATE_SINGLES` every placement is followed by filling naked and hidden singles, which
+/// are undone again on backtrack. Every solution is handed to `callback`, and the ones it accepts or
+/// records are returned.
+pub fn solve_single_thread<
+    const PROPAGATE_SINGLES: bool,
+    P: PuzzleCore + Clone,
+    R: Into<SolutionAction>,
+>(
+    sudoku: &mut P,
+    allowed_candidates: &[CandidateSet; 81],
+    callback: impl FnMut(&P) -> R,
+    is_cancelled: impl Fn() -> bool,
+    index_mapper: impl Fn(usize) -> u8,
+) -> Solutions<P> {
+    solve_single_thread_with_check_interval::<PROPAGATE_SINGLES, _, _>(
+        sudoku,
+        allowed_candidates,
+        callback,
+        is_cancelled,
+        index_mapper,
+        DEFAULT_CHECK_INTERVAL,
+    )
+}
+
+/// Like `solve_single_thread`, but calls `is_cancelled` every `check_interval` search nodes
+pub fn solve_single_thread_with_check_interval<
+    const PROPAGATE_SINGLES: bool,
+    P: PuzzleCore + Clone,
+    R: Into<SolutionAction>,
+>(
+    sudoku: &mut P,
+    allowed_candidates: &[CandidateSet; 81],
+    callback: impl FnMut(&P) -> R,
+    is_cancelled: impl Fn() -> bool,
+    index_mapper: impl Fn(usize) -> u8,
+    check_interval: u32,
+) -> Solutions<P> {
+    solve_single_thread_with_progress::<PROPAGATE_SINGLES, _, _>(
+        sudoku,
+        allowed_candidates,
+        callback,
+        is_cancelled,
+        index_mapper,
+        check_interval,
+        |_| {},
+    )
+}
+
+/// Like `solve_single_thread_with_check_interval`, and also reports its progress to `progress`
+/// every `check_interval` search nodes
+pub fn solve_single_thread_with_progress<
+    const PROPAGATE_SINGLES: bool,
+    P: PuzzleCore + Clone,
+    R: Into<SolutionAction>,
+>(
+    sudoku: &mut P,
+    allowed_candidates: &[CandidateSet; 81],
+    mut callback: impl FnMut(&P) -> R,
+    is_cancelled: impl Fn() -> bool,
+    index_mapper: impl Fn(usize) -> u8,
+    check_interval: u32,
+    progress: impl Fn(&SolveProgress),
+) -> Solutions<P> {
+    let mut stack: [CandidateSetIterator; 81] = [CandidateSetIterator::empty(); _];
+    //Number of candidates each stack frame started out with
+    let mut widths: [u8; 81] = [0; _];
+    let mut stack_idx = usize::MAX;
+    let mut counter = 0;
+    let nodes = core::cell::Cell::new(0_u64);
+    let mut trail = SinglesTrail::new();
+    //Trail length before the current digit of each stack frame was placed
+    let mut trail_marks: [usize; 81] = [0; _];
+    let mut solutions = Solutions::default();
+    for (k, v) in stack.iter_mut().enumerate() {
+        if !sudoku.is_missing(index_mapper(k)) {
+            *v = CandidateSetIterator::fixed()
+        }
+    }
+
+    let pop_task = |sudoku: &mut P,
+                    stack: &mut [CandidateSetIterator; 81],
+                    widths: &[u8; 81],
+                    stack_idx: &mut usize,
+                    counter: &mut u32,
+                    trail: &mut SinglesTrail,
+                    trail_marks: &[usize; 81]|
+     -> bool {
+        *stack_idx = (*stack_idx).min(80);
+        loop {
+            while *stack_idx <= 80
+                && (stack[*stack_idx].is_empty()
+                    || stack[*stack_idx].is_fixed()
+                    || stack[*stack_idx].is_forced())
+            {
+                if stack[*stack_idx].is_empty() {
+                    if PROPAGATE_SINGLES {
+                        trail.undo(sudoku, trail_marks[*stack_idx]);
+                    }
+                    sudoku.set(index_mapper(*stack_idx), 0);
+                }
+                *stack_idx = stack_idx.wrapping_sub(1);
+            }
+            if *stack_idx > 80 {
+                //Stack is empty, no more tasks
+                return false;
+            }
+            *counter += 1;
+            if *counter >= check_interval {
+                *counter = 0;
+                if is_cancelled() {
+                    return false;
+                }
+                nodes.set(nodes.get() + check_interval as u64);
+                //The frame on top is between two digits, the ones below are searching one
+                let frames = (0..=*stack_idx)
+                    .filter(|k| !stack[*k].is_fixed() && !stack[*k].is_forced())
+                    .map(|k| {
+                        let finished = widths[k] as u32 - stack[k].len();
+                        (finished - (k != *stack_idx) as u32, widths[k] as u32)
+                    });
+                progress(&SolveProgress::estimate(nodes.get(), frames));
+            }
+            if PROPAGATE_SINGLES {
+                trail.undo(sudoku, trail_marks[*stack_idx]);
+            }
+            sudoku.set(index_mapper(*stack_idx), stack[*stack_idx].next().unwrap());
+            if SUDOKU_DEBUG {
+                println!("Trying:  {}", SingleLineDisplayAdaptor(sudoku));
+                println!(
+                    "         {}^",
+                    " ".repeat(index_mapper(*stack_idx) as usize)
+                );
+            }
+            //A contradiction moves straight on to the next candidate
+            if !PROPAGATE_SINGLES || trail.propagate(sudoku, allowed_candidates) {
+                return true;
+            }
+        }
+    };
+    let mut push_tasks = |sudoku: &mut P,
+                          stack: &mut [CandidateSetIterator; 81],
+                          widths: &mut [u8; 81],
+                          stack_idx: &mut usize,
+                          trail: &SinglesTrail,
+                          trail_marks: &mut [usize; 81]|
+     -> bool {
+        *stack_idx = (*stack_idx).wrapping_add(1);
+        while *stack_idx <= 80
+            && (stack[*stack_idx].is_fixed()
+                || (PROPAGATE_SINGLES && !sudoku.is_missing(index_mapper(*stack_idx))))
+        {
+            if !stack[*stack_idx].is_fixed() {
+                stack[*stack_idx] = CandidateSetIterator::forced();
+            }
+            *stack_idx += 1;
+        }
+        if *stack_idx > 80 {
+            if solutions.keep(sudoku, callback(sudoku).into()) {
+                return false;
+            }
+        } else {
+            let index = index_mapper(*stack_idx);
+            let candidates = sudoku.get_candidates(index) & allowed_candidates[index as usize];
+            widths[*stack_idx] = candidates.len() as u8;
+            stack[*stack_idx] = candidates.into_iter();
+            trail_marks[*stack_idx] = trail.len;
+        }
+        true
+    };
+
+    if PROPAGATE_SINGLES && !trail.propagate(sudoku, allowed_candidates) {
+        trail.undo(sudoku, 0);
+        return solutions;
+    }
+    push_tasks(
+        sudoku,
+        &mut stack,
+        &mut widths,
+        &mut stack_idx,
+        &trail,
+        &mut trail_marks,
+    );
+    while pop_task(
+        sudoku,
+        &mut stack,
+        &widths,
+        &mut stack_idx,
+        &mut counter,
+        &mut trail,
+        &trail_marks,
+    ) && push_tasks(
+        sudoku,
+        &mut stack,
+        &mut widths,
+        &mut stack_idx,
+        &trail,
+        &mut trail_marks,
+    ) {}
+    if PROPAGATE_SINGLES && stack_idx == usize::MAX {
+        //Search ran out of tasks, leave the puzzle as it was handed in
+        trail.undo(sudoku, 0);
+    }
+    solutions
+}
+
+// Moves the cell with the fewest allowed candidates to the front of `cells` and returns them
+pub(crate) fn fewest_candidates_first<P: PuzzleCore>(
+    sudoku: &P,
+    allowed_candidates: &[CandidateSet; 81],
+    cells: &mut [u8],
+) -> CandidateSet {
+    let mut best = 0;
+    let mut best_candidates = CandidateSet::new();
+    for (k, cell) in cells.iter().enumerate() {
+        let candidates = sudoku.get_candidates(*cell) & allowed_candidates[*cell as usize];
+        if candidates.len() < best_candidates.len() || k == 0 {
+            best = k;
+            best_candidates = candidates;
+            if best_candidates.len() <= 1 {
+                break;
+            }
+        }
+    }
+    cells.swap(0, best);
+    best_candidates
+}
+
+/// Backtracking that picks the empty cell with the fewest candidates at every push instead of
+/// following a fixed cell order
+pub fn solve_single_thread_dynamic<P: PuzzleCore + Clone, R: Into<SolutionAction>>(
+    sudoku: &mut P,
+    allowed_candidates: &[CandidateSet; 81],
+    callback: impl FnMut(&P) -> R,
+    is_cancelled: impl Fn() -> bool,
+) -> Solutions<P> {
+    solve_single_thread_dynamic_with_progress(
+        sudoku,
+        allowed_candidates,
+        callback,
+        is_cancelled,
+        DEFAULT_CHECK_INTERVAL,
+        |_| {},
+    )
+}
+
+/// Like `solve_single_thread_dynamic`, but calls `is_cancelled` and reports its progress to
+/// `progress` every `check_interval` search nodes
+pub fn solve_single_thread_dynamic_with_progress<P: PuzzleCore + Clone, R: Into<SolutionAction>>(
+    sudoku: &mut P,
+    allowed_candidates: &[CandidateSet; 81],
+    mut callback: impl FnMut(&P) -> R,
+    is_cancelled: impl Fn() -> bool,
+    check_interval: u32,
+    progress: impl Fn(&SolveProgress),
+) -> Solutions<P> {
+    // cells[..depth] are the cells of the current stack frames, cells[depth..cell_count] are still empty
+    let mut cells: [u8; 81] = [0; _];
+    let mut cell_count = 0;
+    for index in 0..81 {
+        if sudoku.is_missing(index) {
+            cells[cell_count] = index;
+            cell_count += 1;
+        }
+    }
+    let mut stack: [CandidateSetIterator; 81] = [CandidateSetIterator::empty(); _];
+    //Number of candidates each stack frame started out with
+    let mut widths: [u8; 81] = [0; _];
+    let mut depth = 0;
+    let mut counter = 0;
+    let mut nodes = 0;
+    let mut solutions = Solutions::default();
+
+    loop {
+        if depth == cell_count {
+            if solutions.keep(sudoku, callback(sudoku).into()) {
+                return solutions;
+            }
+        } else {
+            let best_candidates =
+                fewest_candidates_first(sudoku, allowed_candidates, &mut cells[depth..cell_count]);
+            widths[depth] = best_candidates.len() as u8;
+            stack[depth] = best_candidates.into_iter();
+            depth += 1;
+        }
+
+        loop {
+            if depth == 0 {
+                //Stack is empty, no more tasks
+                return solutions;
+            }
+            if let Some(digit) = stack[depth - 1].next() {
+                sudoku.set(cells[depth - 1], digit);
+                break;
+            }
+            sudoku.set(cells[depth - 1], 0);
+            depth -= 1;
+        }
+
+        counter += 1;
+        if counter >= check_interval {
+            counter = 0;
+            if is_cancelled() {
+                return solutions;
+            }
+            nodes += check_interval as u64;
+            //Every frame is searching one of its digits
+            let frames = (0..depth).map(|k| {
+                let finished = widths[k] as u32 - stack[k].len() - 1;
+                (finished, widths[k] as u32)
+            });
+            progress(&SolveProgress::estimate(nodes, frames));
+        }
+        if SUDOKU_DEBUG {
+            println!("Trying:  {}", SingleLineDisplayAdaptor(sudoku));
+            println!("         {}^", " ".repeat(cells[depth - 1] as usize));
+        }
+    }
+}
+
+/// A complete search algorithm that can stand in for another, so backends can be compared and mixed
+pub trait SolverBackend: Send + Sync {
+    /// Calls `callback` with every solution until it returns true or `is_cancelled` does, which
+    /// is called every `check_interval` search nodes
+    fn solve(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: &dyn Fn(&Sudoku) -> bool,
+        is_cancelled: &dyn Fn() -> bool,
+        check_interval: u32,
+    );
+
+    /// Like `solve`, but `callback` accepts, rejects, records or aborts like with `Solver::solve`.
+    /// The backend only sees whether to stop, the solutions are kept here.
+    fn solve_with_actions(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: &mut dyn FnMut(&Sudoku) -> SolutionAction,
+    ) -> Solutions {
+        let callback = core::cell::RefCell::new(callback);
+        let found = core::cell::RefCell::new(Solutions::default());
+        self.solve(
+            sudoku,
+            allowed_candidates,
+            &|s| {
+                let action = (callback.borrow_mut())(s);
+                found.borrow_mut().keep(s, action)
+            },
+            &|| false,
+            DEFAULT_CHECK_INTERVAL,
+        );
+        found.into_inner()
+    }
+
+    /// Whether `solve` returning without being cancelled or stopped by `callback` means it offered
+    /// every solution. The multithreaded solver then stops the other threads on the problem, a
+    /// backend that may give up early only stops itself. On the thread that called `solve`, a
+    /// `DynamicBacktracking` search takes over so the problem is still settled.
+    fn is_exhaustive(&self) -> bool {
+        false
+    }
+
+    /// Like `solve`, and also reports its progress to `progress` every `check_interval` search
+    /// nodes. Backends that cannot estimate how much of the tree they searched report the node
+    /// count alone.
+    fn solve_with_progress(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: &dyn Fn(&Sudoku) -> bool,
+        is_cancelled: &dyn Fn() -> bool,
+        check_interval: u32,
+        progress: &dyn Fn(&SolveProgress),
+    ) {
+        let nodes = core::cell::Cell::new(0);
+        self.solve(
+            sudoku,
+            allowed_candidates,
+            callback,
+            &|| {
+                if is_cancelled() {
+                    return true;
+                }
+                nodes.set(nodes.get() + check_interval as u64);
+                progress(&SolveProgress {
+                    nodes: nodes.get(),
+                    depth: 0,
+                    estimated_fraction: 0.0,
+                });
+                false
+            },
+            check_interval,
+        );
+    }
+
+    /// Counts solutions, stopping early once `limit` are found
+    fn count_solutions(&self, sudoku: &Sudoku, limit: usize) -> usize {
+        self.count_solutions_with_progress(sudoku, limit, &|_| {})
+    }
+
+    /// Like `count_solutions`, and also reports its progress to `progress` every
+    /// `DEFAULT_CHECK_INTERVAL` search nodes
+    fn count_solutions_with_progress(
+        &self,
+        sudoku: &Sudoku,
+        limit: usize,
+        progress: &dyn Fn(&SolveProgress),
+    ) -> usize {
+        let count = core::cell::Cell::new(0);
+        self.solve_with_progress(
+            &mut sudoku.clone(),
+            &ALL_CANDIDATES,
+            &|_| {
+                count.set(count.get() + 1);
+                count.get() >= limit
+            },
+            &|| false,
+            DEFAULT_CHECK_INTERVAL,
+            progress,
+        );
+        count.get()
+    }
+
+    /// Like `solve_with_progress`, but gives up once `limits` run out. Node limits are counted in
+    /// steps of `check_interval`.
+    #[allow(clippy::too_many_arguments)]
+    fn solve_with_limits(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: &dyn Fn(&Sudoku) -> bool,
+        is_cancelled: &dyn Fn() -> bool,
+        check_interval: u32,
+        limits: &SolveLimits,
+        progress: &dyn Fn(&SolveProgress),
+    ) -> SolveOutcome {
+        let start = Instant::now();
+        let nodes = core::cell::Cell::new(0);
+        let solutions = core::cell::Cell::new(0);
+        let limit_reached = core::cell::Cell::new(false);
+        let cancelled = core::cell::Cell::new(false);
+        self.solve_with_progress(
+            sudoku,
+            allowed_candidates,
+            &|s| {
+                solutions.set(solutions.get() + 1);
+                callback(s)
+            },
+            &|| {
+                nodes.set(nodes.get() + check_interval as u64);
+                if limits.is_exceeded(nodes.get()) {
+                    limit_reached.set(true);
+                } else if limits.is_cancelled() || is_cancelled() {
+                    cancelled.set(true);
+                }
+                limit_reached.get() || cancelled.get()
+            },
+            check_interval,
+            progress,
+        );
+        let stats = SolveStats {
+            nodes: nodes.get(),
+            solutions: solutions.get(),
+            elapsed: start.elapsed(),
+        };
+        if limit_reached.get() {
+            SolveOutcome::LimitReached(stats)
+        } else if cancelled.get() {
+            SolveOutcome::Cancelled(stats)
+        } else {
+            SolveOutcome::Finished(stats)
+        }
+    }
+}
+
+/// `solve_single_thread` with a fixed cell order
+pub struct Backtracking {
+    pub index_mapper: fn(usize) -> u8,
+    pub propagate_singles: bool,
+}
+
+impl Default for Backtracking {
+    fn default() -> Self {
+        Backtracking {
+            index_mapper: |x| x as u8,
+            propagate_singles: false,
+        }
+    }
+}
+
+impl SolverBackend for Backtracking {
+    fn solve(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: &dyn Fn(&Sudoku) -> bool,
+        is_cancelled: &dyn Fn() -> bool,
+        check_interval: u32,
+    ) {
+        self.solve_with_progress(
+            sudoku,
+            allowed_candidates,
+            callback,
+            is_cancelled,
+            check_interval,
+            &|_| {},
+        );
+    }
+
+    fn solve_with_progress(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: &dyn Fn(&Sudoku) -> bool,
+        is_cancelled: &dyn Fn() -> bool,
+        check_interval: u32,
+        progress: &dyn Fn(&SolveProgress),
+    ) {
+        if self.propagate_singles {
+            solve_single_thread_with_progress::<true, _, _>(
+                sudoku,
+                allowed_candidates,
+                callback,
+                is_cancelled,
+                self.index_mapper,
+                check_interval,
+                progress,
+            );
+        } else {
+            solve_single_thread_with_progress::<false, _, _>(
+                sudoku,
+                allowed_candidates,
+                callback,
+                is_cancelled,
+                self.index_mapper,
+                check_interval,
+                progress,
+            );
+        }
+    }
+
+    fn is_exhaustive(&self) -> bool {
+        true
+    }
+}
+
+/// `solve_single_thread_dynamic`, fewest candidates first
+pub struct DynamicBacktracking;
+
+impl SolverBackend for DynamicBacktracking {
+    fn solve(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: &dyn Fn(&Sudoku) -> bool,
+        is_cancelled: &dyn Fn() -> bool,
+        check_interval: u32,
+    ) {
+        self.solve_with_progress(
+            sudoku,
+            allowed_candidates,
+            callback,
+            is_cancelled,
+            check_interval,
+            &|_| {},
+        );
+    }
+
+    fn solve_with_progress(
+        &self,
+        sudoku: &mut Sudoku,
+        allowed_candidates: &[CandidateSet; 81],
+        callback: &dyn Fn(&Sudoku) -> bool,
+        is_cancelled: &dyn Fn() -> bool,
+        check_interval: u32,
+        progress: &dyn Fn(&SolveProgress),
+    ) {
+        solve_single_thread_dynamic_with_progress(
+            sudoku,
+            allowed_candidates,
+            callback,
+            is_cancelled,
+            check_interval,
+            progress,
+        );
+    }
+
+    fn is_exhaustive(&self) -> bool {
+        true
+    }
+}
+
+pub fn init() {
+    INIT.call_once(|| unsafe {
+        PROGRAM_START_TIME.set(Instant::now());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::{BufRead, BufReader};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    macro_rules! test_helper {
+        ($solver:ident, $did_solve:ident, $sudoku_str:expr) => {{
+            let mut sudoku: Sudoku = $sudoku_str.into();
+            $did_solve.store(false, Ordering::Release);
+            assert!(!sudoku.is_valid());
+            $solver
+                .solve(&mut sudoku, callback_expecting_generic(None, &$did_solve))
+                .unwrap();
+            assert!($did_solve.load(Ordering::Acquire));
+        }};
+    }
+
+    macro_rules! timed_test_helper {
+        ($solver:ident, $did_solve:ident, $sudoku_str:expr, $time:ident) => {{
+            let mut sudoku: Sudoku = $sudoku_str.into();
+            $did_solve.store(false, Ordering::Release);
+            assert!(!sudoku.is_valid());
+            let solver_start = Instant::now();
+            $solver
+                .solve(&mut sudoku, callback_expecting_generic(None, &$did_solve))
+                .unwrap();
+            $time += solver_start.elapsed();
+            assert!($did_solve.load(Ordering::Acquire));
+        }};
+    }
+
+    macro_rules! test_helper_with_answer {
+        ($solver:ident, $did_solve:ident, $sudoku_str:expr, $solution:expr) => {{
+            let mut sudoku: Sudoku = $sudoku_str.into();
+            $did_solve.store(false, Ordering::Release);
+            assert!(!sudoku.is_valid());
+            $solver
+                .solve(
+                    &mut sudoku,
+                    callback_expecting_generic(Some($solution.into()), &$did_solve),
+                )
+                .unwrap();
+            assert!($did_solve.load(Ordering::Acquire));
+        }};
+    }
+
+    fn callback_expecting_generic(
+        solution: Option<Sudoku>,
+        atomic: &AtomicBool,
+    ) -> impl Fn(&Sudoku) -> bool + Send {
+        move |candidate: &Sudoku| {
+            assert!(candidate.is_valid());
+            if match &solution {
+                None => true,
+                Some(sol) => *candidate == *sol,
+            } {
+                atomic.store(true, Ordering::Release);
+                true
+            } else {
+                false
+            }
+        }
+    }
+
+    #[test]
+    fn test_basic() {
+        init();
+        let did_solve = AtomicBool::new(false);
+        let x = with_multithreaded_solver(|solver| {
+            test_helper_with_answer!(solver, did_solve, "351897264897642135642351789265139478784265913139784526918476352523918647476503891","351897264897642135642351789265139478784265913139784526918476352523918647476523891");
+            test_helper_with_answer!(solver, did_solve, "987645213132978465654123798579364182346812950821597634495236871263781549718459326","987645213132978465654123798579364182346812957821597634495236871263781549718459326");
+            test_helper_with_answer!(solver, did_solve, "537864129864912375912537648629175483175348296348629751783496512201783964496251837","537864129864912375912537648629175483175348296348629751783496512251783964496251837");
+            test_helper_with_answer!(solver, did_solve, "275396418481275369396481257813754926962813745754962801627139584139548672548627193","275396418481275369396481257813754926962813745754962831627139584139548672548627193");
+            test_helper_with_answer!(solver, did_solve, "835791426642853197719624358961248735573916842084537619458379261126485973397162584","835791426642853197719624358961248735573916842284537619458379261126485973397162584");
+            test_helper_with_answer!(solver, did_solve, "281659734659703218743281695516497823497832561832516479378125946125964387964378152","281659734659743218743281695516497823497832561832516479378125946125964387964378152");
+            test_helper_with_answer!(solver, did_solve, "849756123756213489213849576532198647198467350467532918625381794974625831381974265","849756123756213489213849576532198647198467352467532918625381794974625831381974265");
+            test_helper_with_answer!(solver, did_solve, "721645839564893271389217654693178425817452963245936187436789512978521346150364798","721645839564893271389217654693178425817452963245936187436789512978521346152364798");
+            test_helper_with_answer!(solver, did_solve, "971483526834562179625197438583216704497358261162749385258671943716934852349825617","971483526834562179625197438583216794497358261162749385258671943716934852349825617");
+            test_helper_with_answer!(solver, did_solve, "971483562526971438403526917219734856734865291865219743147358629358692174692147385","971483562526971438483526917219734856734865291865219743147358629358692174692147385");
+            test_helper_with_answer!(solver, did_solve, "824517396157936824396284157719362485485179630632845719973628541541793268268451973","824517396157936824396284157719362485485179632632845719973628541541793268268451973");
+            test_helper_with_answer!(solver, did_solve, "849756231132948756657231948284569317713482569965317482571823694328694170496175823","849756231132948756657231948284569317713482569965317482571823694328694175496175823");
+            test_helper_with_answer!(solver, did_solve, "215364987879215436364879521531486792927531648486927150752143869143698275698752314","215364987879215436364879521531486792927531648486927153752143869143698275698752314");
+            test_helper_with_answer!(solver, did_solve, "374612589612958743958374126435267018267891354891435672549723861723186495186549237","374612589612958743958374126435267918267891354891435672549723861723186495186549237");
+            test_helper_with_answer!(solver, did_solve, "127936548603485127548271693275319486486752931931864275869547312312698754754123869","127936548693485127548271693275319486486752931931864275869547312312698754754123869");
+            test_helper_with_answer!(solver, did_solve, "374598261621437895985162734198246357537819642462753918219674583746305129853921476","374598261621437895985162734198246357537819642462753918219674583746385129853921476");
+            test_helper_with_answer!(solver, did_solve, "914623758758194203263578914547319682682457139139862547876945321321786495495231876","914623758758194263263578914547319682682457139139862547876945321321786495495231876");
+            test_helper_with_answer!(solver, did_solve, "768314295143029768295876143481293657657148932932765481319652874526487319874931526","768314295143529768295876143481293657657148932932765481319652874526487319874931526");
+            test_helper_with_answer!(solver, did_solve, "914386257386257914257914386172849563560172849849563172698735421735421698421698735","914386257386257914257914386172849563563172849849563172698735421735421698421698735");
+            test_helper_with_answer!(solver, did_solve, "987643521634215897251078364542891736819367452376524189128739645465182973793456218","987643521634215897251978364542891736819367452376524189128739645465182973793456218");
+            test_helper_with_answer!(solver, did_solve, "561487392309651874847239516136574928754892163982316745693145287415728639278963451","561487392329651874847239516136574928754892163982316745693145287415728639278963451");
+            test_helper_with_answer!(solver, did_solve, "519837246264915783738462591187243659342596178695781324823654907971328465456179832","519837246264915783738462591187243659342596178695781324823654917971328465456179832");
+            test_helper_with_answer!(solver, did_solve, "281673459736549218495128763129867534543912687678354192357491826062735941914286375","281673459736549218495128763129867534543912687678354192357491826862735941914286375");
+            test_helper_with_answer!(solver, did_solve, "281673495594182673376495182815267349043518267762349518159826734628734951437951826","281673495594182673376495182815267349943518267762349518159826734628734951437951826");
+            test_helper_with_answer!(solver, did_solve, "721645983938271456465398712142956837596783124873412569254869371310524698689137245","721645983938271456465398712142956837596783124873412569254869371317524698689137245");
+            test_helper_with_answer!(solver, did_solve, "721645983938217546564389712219476358385192674647853291193720865856931427472568139","721645983938217546564389712219476358385192674647853291193724865856931427472568139");
+            test_helper_with_answer!(solver, did_solve, "000720030007006820106008709003091000580407200000000006840650010600143900005000402","958724631437916825126538749763291584581467293294385176849652317672143958315879462");
+            test_helper_with_answer!(solver, did_solve, "900724030030050784100083000093400006001208009000900370016000040304860020200040000","958724631632159784147683592893475216761238459425916378516392847374861925289547163");
+            test_helper_with_answer!(solver, did_solve, "002700500804020970960800420500078040048000057006430200009000000210007004000040795","132794586854326971967851423521678349348219657796435218479583162215967834683142795");
+            test_helper_with_answer!(solver, did_solve, "102700580006005790709106042200060400560008210400000000070200000604050003000647008","132794586846325791759186342287961435563478219491532867978213654624859173315647928");
+            test_helper_with_answer!(solver, did_solve, "009700060100068009000294507000809600800000004030000982900005020605020093007940056","459731268172568349368294517724819635896352174531476982943685721685127493217943856");
+            test_helper_with_answer!(solver, did_solve, "000080930379500040000073500004300070810090000700406001107609854040700000926000003","265184937379562148481973526654318279813297465792456381137629854548731692926845713");
+            test_helper_with_answer!(solver, did_solve, "000500341105074600300100000409700100000090004600021970501000706800900425740005000","976582341125374698384169257439756182217893564658421973591248736863917425742635819");
+            test_helper_with_answer!(solver, did_solve, "307019400800452000000360000040700985730080200080240730620070004000094000090000170","367819452819452367452367819246731985731985246985246731623178594178594623594623178");
+            test_helper_with_answer!(solver, did_solve, "000009400500207001900534760800000674000490000154000020016050000289601000405002100","367819452548267391921534768893125674672493815154786923716358249289641537435972186");
+            test_helper_with_answer!(solver, did_solve, "006975213507631849093080675052006000000520086004890150240709000065100008001060004","486975213527631849193284675852416937719523486634897152248759361365142798971368524");
+            test_helper_with_answer!(solver, did_solve, "003095027900087430700200001108000005009802010352014080830970002507428190090506748","413695827925187436786243951148369275679852314352714689834971562567428193291536748");
+            test_helper_with_answer!(solver, did_solve, "652070381000680040038100690003800704829040016574260908000008170085019403007420000","652974381791683245438152697163895724829347516574261938946538172285719463317426859");
+            test_helper_with_answer!(solver, did_solve, "052304000437180920600500400086203107174065302300700008003018250060900014801052030","952374681437186925618529473586293147174865392329741568793418256265937814841652739");
+            test_helper_with_answer!(solver, did_solve, "241396080000085624805720100004009500176008900090030008020900370610072859059063200","241396785937185624865724193384219567176548932592637418428951376613472859759863241");
+            test_helper_with_answer!(solver, did_solve, "000074008401005320673102540060040030198023006034569080000007800047018265029450100","952374618481695327673182549765841932198723456234569781516237894347918265829456173");
+            test_helper_with_answer!(solver, did_solve, "135826000700000520000070001000000034073000215081243076006085740042967153057402609","135826497764139528298574361629751834473698215581243976916385742842967153357412689");
+            test_helper_with_answer!(solver, did_solve, "570362100064070030931485620020600094603759200095210000306007902009500008782006000","578362149264971835931485627127638594643759281895214376356847912419523768782196453");
+            test_helper_with_answer!(solver, did_solve, "060314057470650200305028694003000460740580000891402305007040500604801002000900746","269314857478659213315728694523197468746583129891462375937246581654871932182935746");
+            test_helper_with_answer!(solver, did_solve, "752630914149257683836941275983412567014570398075389421367894152498125736521763849","752638914149257683836941275983412567214576398675389421367894152498125736521763849");
+            test_helper_with_answer!(solver, did_solve, "248769510967513842315842769659380204183274956472956381794635128536128097821497635","248769513967513842315842769659381274183274956472956381794635128536128497821497635");
+            test_helper_with_answer!(solver, did_solve, "689124753357608241042075986401539628826417395593862174734956812965281437218743569","689124753357698241142375986471539628826417395593862174734956812965281437218743569");
+            test_helper_with_answer!(solver, did_solve, "841325967796418325532967418674831502059746831103592746927684153315279604468153279","841325967796418325532967418674831592259746831183592746927684153315279684468153279");
+            test_helper_with_answer!(solver, did_solve, "801230479497185326362749815613492587508361942924857631239574160186923754745618293","851236479497185326362749815613492587578361942924857631239574168186923754745618293");
+            test_helper_with_answer!(solver, did_solve, "519823046764519832803764591351482967482976315976350428197035684235648179648197253","519823746764519832823764591351482967482976315976351428197235684235648179648197253");
+            test_helper_with_answer!(solver, did_solve, "186275934725934816394816075067523491901687523253491687418762359672359048539148762","186275934725934816394816275867523491941687523253491687418762359672359148539148762");
+            test_helper_with_answer!(solver, did_solve, "275391640406752913139864527528179364643285791917436852792613485061548279850927136","275391648486752913139864527528179364643285791917436852792613485361548279854927136");
+            test_helper_with_answer!(solver, did_solve, "169284735208375916350196824730951682826743591915862473681427359593618247472039168","169284735248375916357196824734951682826743591915862473681427359593618247472539168");
+            test_helper_with_answer!(solver, did_solve, "001000000005000000900000200000040000000057000000310402040500630600400805009000000","261794358385621947974835216138246579426957183597318462742589631613472895859163724");
+            test_helper_with_answer!(solver, did_solve, "009000132060000000000800005100000008000100073000600000201000000500010000900060400","879456132465321789312897645154732968698145273723689514241973856586214397937568421");
+
+            test_helper!(solver, did_solve, "519872436643159827782463195495217368127386954836945271354791682268534719971028543");
+            test_helper!(solver, did_solve, "015369874748215936369748521497182365653497218182653749934871652526934187871526493");
+            test_helper!(solver, did_solve, "675491382238675914091238756984352167716984523352716849563147298147829635829563471");
+            test_helper!(solver, did_solve, "206473819819625734734981256567834192192756348348219567925367481481592673673148925");
+            test_helper!(solver, did_solve, "186293547540186293293547186435871962871962435962435871758619324324758619619324758");
+            test_helper!(solver, did_solve, "613574928475298136892316754549182673281763495367945812734859261120437589958621347");
+            test_helper!(solver, did_solve, "459821367281367549637549821765498213123075498948213675892136754316754982574982136");
+            test_helper!(solver, did_solve, "914562078256873941387149265549628137173495826862731594695287413728314659431956782");
+            test_helper!(solver, did_solve, "186293745547681293392745681658912437219437856734856912961324578403578169875169324");
+            test_helper!(solver, did_solve, "070030000000012000002005019050020073700000105140000906200054008630109502005267300");
+            test_helper!(solver, did_solve, "924000305010900280080007916200090700000704021030100590602010007500870100000006000");
+            test_helper!(solver, did_solve, "459063001000040000000095030000802640940000710607910520001600070074080000800030102");
+            test_helper!(solver, did_solve, "000070840080004027040002610090300750530006480000040396900081004060500900872000000");
+            test_helper!(solver, did_solve, "000489700000000809090070020000097000100804902028100003536008010082001305009003608");
+            test_helper!(solver, did_solve, "450063280300200000821590000703082050000037020000045000180009062000320010002050007");
+            test_helper!(solver, did_solve, "400703080000000406680400009005300000093820005064005300070530600031679050500280000");
+            test_helper!(solver, did_solve, "004680000300205419050930000230508001040190850000760090090020537000000006600050020");
+            test_helper!(solver, did_solve, "040000000000020740230400608020057900980000100001068000000502067800714092500600314");
+            test_helper!(solver, did_solve, "608040050300506097905000300030095208590700640004063010007001004800604500000000060");
+            test_helper!(solver, did_solve, "107008000693000020048000000980301460360052781500806230030500008700004006010000000");
+            test_helper!(solver, did_solve, "675321984312498576489657213967513042531284769248976135753142698896735421124869357");
+            test_helper!(solver, did_solve, "851276394726493185943581672217649853469358721538127946102764539674935218395812467");
+            test_helper!(solver, did_solve, "519728436728634915634519827347196582196285743285347691963851274851472369472963108");
+            test_helper!(solver, did_solve, "849673512637015498251894376476321985598467123312589764185946237964732851723158649");
+            test_helper!(solver, did_solve, "374289561298561347516347289985136724742958136163724958637492815851673492429815670");
+            test_helper!(solver, did_solve, "613429875924758631857316942279835164538641297146972580782563419491287356365194728");
+            test_helper!(solver, did_solve, "186352794235947186094861235342679851851423967967518342619285473473196528528734619");
+            test_helper!(solver, did_solve, "914738526783625049652941837495317682268459713371286954139872465546193278827564391");
+            test_helper!(solver, did_solve, "140627359359148627627359148296583471471296583583471296815764932764932815932815764");
+            test_helper!(solver, did_solve, "694513287531782946728649315172896534869435721453127698945371862317208459286954173");
+            test_helper!(solver, did_solve, "369241078758396124014785639876913245931452786425867391683129457192574863547638912");
+            test_helper!(solver, did_solve, "274638951638159472159274836782396145396541087541782693867913524425807319913425768");
+            test_helper!(solver, did_solve, "357194628419826375682573491061387954738459216945012783593241867124768539876935142");
+            test_helper!(solver, did_solve, "624579183795183462831462579573218946182946357469357218358621794216794835907830621");
+            test_helper!(solver, did_solve, "758421396963087214142639875314968527275143689896752431427316958631895742589274160");
+            test_helper!(solver, did_solve, "624579381597381642318642579839416725052893416461725893143067958276958134985134267");
+            test_helper!(solver, did_solve, "471368925925047683683592471816923754239475816754681239148236597597814362362759048");
+            test_helper!(solver, did_solve, "436987152978251364215463789182345697769128543354796821891532476647819235523074908");
+            test_helper!(solver, did_solve, "231407598859123746674985312346879025798251463512634987925316874487592631163748259");
+            test_helper!(solver, did_solve, "593280761284617359617593428468751932329468175751329846942876513876135294135942607");
+            test_helper!(solver, did_solve, "624831597801795426795624138257346819918257643340918752472163985163589274589472361");
+            test_helper!(solver, did_solve, "152038746398647215467512839571293468923864157084751392219386574836475921745129683");
+            test_helper!(solver, did_solve, "697825413341976825582413076925138764813764259476259138164597382238601597759382641");
+            test_helper!(solver, did_solve, "218435796345796128976128430459672813183549672762813549597261384834907261621384957");
+            test_helper!(solver, did_solve, "539824176761359428284671953690432781342187569817965034478516392156293847923748615");
+            test_helper!(solver, did_solve, "539824176428671539176935428853246917917350642642719853285467391760193285391582764");
+            test_helper!(solver, did_solve, "524719638368542971791086254870423519159867342432195786617238495945671823283954167");
+            test_helper!(solver, did_solve, "957812634281306957634579281468937125793251468025684793519428376842763519376195842");
+            test_helper!(solver, did_solve, "134258697967134528258967314813725906496013275725496183349581762672349851581672439");
+            
+
+            42
+        });
+        assert_eq!(42, x);
+    }
+
+    #[test]
+    fn test_restricted_candidates() {
+        init();
+        let did_solve = AtomicBool::new(false);
+        let mut allowed = ALL_CANDIDATES;
+        allowed[0] = CandidateSet(1 << 6);
+        allowed[80] = CandidateSet(1 << 2 | 1 << 4);
+        let expect_restricted = |s: &Sudoku| {
+            assert!(s.is_valid());
+            assert_eq!(s.get(0), 7);
+            assert!(s.get(80) == 3 || s.get(80) == 5);
+            did_solve.store(true, Ordering::Release);
+            true
+        };
+
+        solve_single_thread::<false, _, _>(
+            &mut Sudoku::from([0_u8; 81]),
+            &allowed,
+            expect_restricted,
+            || false,
+            |x| x as u8,
+        );
+        assert!(did_solve.load(Ordering::Acquire));
+
+        did_solve.store(false, Ordering::Release);
+        with_multithreaded_solver(|solver| {
+            solver
+                .solve_with_candidates(&mut Sudoku::from([0_u8; 81]), &allowed, expect_restricted)
+                .unwrap();
+        });
+        assert!(did_solve.load(Ordering::Acquire));
+
+        // The only solution has a 9 in the first cell, excluding it leaves nothing to find
+        let mut sudoku: Sudoku =
+            "000720030007006820106008709003091000580407200000000006840650010600143900005000402"
+                .into();
+        let mut allowed = ALL_CANDIDATES;
+        allowed[0] &= !(1 << 8);
+        solve_single_thread::<false, _, _>(
+            &mut sudoku,
+            &allowed,
+            |_| -> bool { panic!() },
+            || false,
+            |x| x as u8,
+        );
+    }
+
+    #[test]
+    fn test_check_interval() {
+        init();
+        // The only solution has a 9 in the first cell, excluding it leaves nothing to find
+        let mut sudoku: Sudoku =
+            "000720030007006820106008709003091000580407200000000006840650010600143900005000402"
+                .into();
+        let mut allowed = ALL_CANDIDATES;
+        allowed[0] &= !(1 << 8);
+        let checks = core::cell::Cell::new(0);
+        solve_single_thread_with_check_interval::<false, _, _>(
+            &mut sudoku,
+            &allowed,
+            |_| -> bool { panic!() },
+            || {
+                checks.set(checks.get() + 1);
+                false
+            },
+            |x| x as u8,
+            10,
+        );
+        assert!(checks.get() > 0);
+
+        for backend in [
+            &Backtracking::default() as &dyn SolverBackend,
+            &DynamicBacktracking,
+            &dlx::DancingLinks,
+            &backjump::Backjumping {
+                learn_nogoods: false,
+            },
+        ] {
+            //Every solution takes at least one node, so five checks leave room for at most 50
+            let checks = core::cell::Cell::new(0);
+            let solutions = core::cell::Cell::new(0);
+            backend.solve(
+                &mut Sudoku::from("0"),
+                &ALL_CANDIDATES,
+                &|_| {
+                    solutions.set(solutions.get() + 1);
+                    false
+                },
+                &|| {
+                    checks.set(checks.get() + 1);
+                    checks.get() >= 5
+                },
+                10,
+            );
+            assert_eq!(checks.get(), 5);
+            assert!(solutions.get() <= 50);
+        }
+
+        //Node limits are counted in steps of the interval, a backend used to overshoot by the default
+        let pool = SolverPool::builder().threads(2).build().unwrap();
+        pool.set_portfolio(vec![Arc::new(dlx::DancingLinks)]);
+        pool.set_check_interval(10);
+        let limits = SolveLimits {
+            max_nodes: Some(1000),
+            ..SolveLimits::default()
+        };
+        let (outcome, _) = pool
+            .solve_with_limits(&mut Sudoku::from("0"), &ALL_CANDIDATES, |_| false, &limits)
+            .unwrap();
+        assert!(matches!(outcome, SolveOutcome::LimitReached(_)));
+        assert!(outcome.stats().nodes < DEFAULT_CHECK_INTERVAL as u64);
+    }
+
+    // Singles propagation has to find the same solutions as plain backtracking under any masks, and
+    // leave the puzzle as it was handed in
+    #[test]
+    fn test_singles_with_restricted_candidates() {
+        init();
+        let solution: Sudoku =
+            "958724631437916825126538749763291584581467293294385176849652317672143958315879462"
+                .into();
+        let mut state = 31;
+        for _ in 0..200 {
+            let mut puzzle = solution.clone();
+            for _ in 0..45 {
+                puzzle.set((next_random(&mut state) % 81) as u8, 0);
+            }
+            let mut allowed = ALL_CANDIDATES;
+            for _ in 0..30 {
+                let index = (next_random(&mut state) % 81) as usize;
+                allowed[index] &= !(1 << (next_random(&mut state) % 9));
+            }
+            let plain = solve_single_thread::<false, _, _>(
+                &mut puzzle.clone(),
+                &allowed,
+                |_| SolutionAction::Record,
+                || false,
+                |x| x as u8,
+            );
+            let mut propagated = puzzle.clone();
+            let singles = solve_single_thread::<true, _, _>(
+                &mut propagated,
+                &allowed,
+                |_| SolutionAction::Record,
+                || false,
+                |x| x as u8,
+            );
+            assert!(propagated == puzzle);
+            assert_eq!(singles.recorded.len(), plain.recorded.len());
+            assert!(singles.recorded.iter().all(|s| plain.recorded.contains(s)));
+        }
+    }
+
+    // Helper threads that exhausted an unsolvable problem have to sleep until the next one is
+    // queued instead of searching it again
+    #[test]
+    fn test_unsolvable_then_solvable() {
+        init();
+        let did_solve = AtomicBool::new(false);
+        let puzzle: Sudoku =
+            "000720030007006820106008709003091000580407200000000006840650010600143900005000402"
+                .into();
+        let solution: Sudoku =
+            "958724631437916825126538749763291584581467293294385176849652317672143958315879462"
+                .into();
+        let mut allowed = ALL_CANDIDATES;
+        allowed[0] &= !(1 << 8);
+        with_multithreaded_solver(|solver| {
+            solver.set_check_interval(1000);
+            for _ in 0..3 {
+                did_solve.store(false, Ordering::Release);
+                solver
+                    .solve_with_candidates(
+                        &mut puzzle.clone(),
+                        &allowed,
+                        callback_expecting_generic(None, &did_solve),
+                    )
+                    .unwrap();
+                assert!(!did_solve.load(Ordering::Acquire));
+
+                solver
+                    .solve(
+                        &mut puzzle.clone(),
+                        callback_expecting_generic(Some(solution.clone()), &did_solve),
+                    )
+                    .unwrap();
+                assert!(did_solve.load(Ordering::Acquire));
+            }
+        });
+    }
+
+    #[test]
+    fn test_ordering_stats() {
+        init();
+        let did_solve = AtomicBool::new(false);
+        let puzzles = [
+            "000720030007006820106008709003091000580407200000000006840650010600143900005000402",
+            "900724030030050784100083000093400006001208009000900370016000040304860020200040000",
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003",
+        ];
+        with_multithreaded_solver(|solver| {
+            for puzzle in puzzles {
+                did_solve.store(false, Ordering::Release);
+                solver
+                    .solve(
+                        &mut Sudoku::from(puzzle),
+                        callback_expecting_generic(None, &did_solve),
+                    )
+                    .unwrap();
+                assert!(did_solve.load(Ordering::Acquire));
+            }
+            let stats = solver.ordering_stats();
+            assert_eq!(
+                stats.iter().map(|s| s.wins).sum::<u32>(),
+                puzzles.len() as u32
+            );
+            assert!(stats.iter().all(|s| s.wins <= s.runs));
+            assert!(stats.iter().any(|s| s.win_rate() > 0.0));
+        });
+
+        let mut shared_context = SharedContext {
+            current_problem: Sudoku::from("0"),
+            current_allowed_candidates: ALL_CANDIDATES,
+            slot_orderings: vec![0, 1, 2],
+            ordering_stats: CELL_ORDERINGS.map(|ordering| OrderingStats {
+                name: ordering.name,
+                runs: 4,
+                wins: 0,
+            }),
+            adaptive_orderings: true,
+            random_orderings: None,
+            portfolio: Vec::new(),
+            current_limits: SolveLimits::default(),
+            progress_callback: None,
+            jobs: VecDeque::new(),
+            next_job_id: 0,
+        };
+        shared_context.ordering_stats[5].wins = 3;
+        shared_context.ordering_stats[7].runs = 0;
+        shared_context.reassign_orderings();
+        assert_eq!(shared_context.slot_orderings, [5, 7, 0]);
+    }
+
+    #[test]
+    fn test_random_orderings() {
+        init();
+        let order = random_cell_order(1);
+        let mut sorted = order;
+        sorted.sort();
+        assert_eq!(sorted, ROW_WISE);
+        assert_eq!(order, random_cell_order(1));
+        assert_ne!(order, random_cell_order(2));
+        assert_eq!(
+            (1..=15).map(luby).collect::<Vec<_>>(),
+            [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]
+        );
+
+        let did_solve = AtomicBool::new(false);
+        let puzzle: Sudoku =
+            "000720030007006820106008709003091000580407200000000006840650010600143900005000402"
+                .into();
+        let solution: Sudoku =
+            "958724631437916825126538749763291584581467293294385176849652317672143958315879462"
+                .into();
+        let mut allowed = ALL_CANDIDATES;
+        allowed[0] &= !(1 << 8);
+        with_multithreaded_solver(|solver| {
+            //Small budgets so the searches actually restart
+            solver.set_check_interval(10);
+            solver.set_random_orderings(Some(RandomOrderings {
+                seed: 7,
+                restart_base: Some(20),
+            }));
+            for _ in 0..3 {
+                did_solve.store(false, Ordering::Release);
+                solver
+                    .solve(
+                        &mut puzzle.clone(),
+                        callback_expecting_generic(Some(solution.clone()), &did_solve),
+                    )
+                    .unwrap();
+                assert!(did_solve.load(Ordering::Acquire));
+
+                //Restarts must not keep an exhausted search going forever
+                did_solve.store(false, Ordering::Release);
+                solver
+                    .solve_with_candidates(
+                        &mut puzzle.clone(),
+                        &allowed,
+                        callback_expecting_generic(None, &did_solve),
+                    )
+                    .unwrap();
+                assert!(!did_solve.load(Ordering::Acquire));
+            }
+            assert!(solver.ordering_stats().iter().all(|s| s.runs == 0));
+        });
+    }
+
+    #[test]
+    fn test_portfolio() {
+        init();
+        let did_solve = AtomicBool::new(false);
+        let puzzle: Sudoku =
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003"
+                .into();
+        let solution: Sudoku =
+            "265184937379562148481973526654318279813297465792456381137629854548731692926845713"
+                .into();
+        let portfolios: [Vec<Arc<dyn SolverBackend>>; 4] = [
+            vec![Arc::new(dlx::DancingLinks)],
+            vec![Arc::new(DynamicBacktracking)],
+            vec![Arc::new(Backtracking {
+                propagate_singles: true,
+                ..Backtracking::default()
+            })],
+            vec![
+                Arc::new(Backtracking::default()),
+                Arc::new(dlx::DancingLinks),
+                Arc::new(DynamicBacktracking),
+            ],
+        ];
+        with_multithreaded_solver(|solver| {
+            for portfolio in portfolios.clone() {
+                solver.set_portfolio(portfolio);
+                did_solve.store(false, Ordering::Release);
+                solver
+                    .solve(
+                        &mut puzzle.clone(),
+                        callback_expecting_generic(Some(solution.clone()), &did_solve),
+                    )
+                    .unwrap();
+                assert!(did_solve.load(Ordering::Acquire));
+            }
+        });
+    }
+
+    #[test]
+    fn test_solver_pool() {
+        init();
+        let puzzle: Sudoku =
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003"
+                .into();
+        let solution: Sudoku =
+            "265184937379562148481973526654318279813297465792456381137629854548731692926845713"
+                .into();
+        //Only orderings that fill the first row early find out quickly that the last cell of it has
+        //no digit left, most others search for ages
+        let unsolvable: Sudoku =
+            "123456780000000009000000000000000000000000000000000000000000000000000000000000000"
+                .into();
+        let pool = Arc::new(SolverPool::builder().threads(4).build().unwrap());
+        //Adaptive orderings could hand the threads only the orderings that solve `puzzle` best,
+        //fixed ones keep row-wise on one of them
+        pool.set_adaptive_orderings(false);
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                let pool = pool.clone();
+                let (puzzle, solution, unsolvable) = (&puzzle, &solution, &unsolvable);
+                scope.spawn(move || {
+                    for _ in 0..5 {
+                        let did_solve = AtomicBool::new(false);
+                        pool.solve(
+                            &mut puzzle.clone(),
+                            callback_expecting_generic(Some(solution.clone()), &did_solve),
+                        )
+                        .unwrap();
+                        assert!(did_solve.load(Ordering::Acquire));
+                        pool.solve(&mut unsolvable.clone(), |_| -> bool { panic!() })
+                            .unwrap();
+                    }
+                });
+            }
+        });
+        //The last handle joins the helper threads
+        drop(Arc::into_inner(pool).unwrap());
+    }
+
+    #[test]
+    fn test_exhausted_search() {
+        init();
+        //The calling thread fills the grid from the back and would search for ages before it gets
+        //to the first row, the helper thread runs into the bad cell right away
+        let unsolvable: Sudoku =
+            "123456780000000009000000000000000000000000000000000000000000000000000000000000000"
+                .into();
+        let pool = SolverPool::builder().threads(2).build().unwrap();
+        pool.set_portfolio(vec![
+            Arc::new(Backtracking {
+                index_mapper: |x| 80 - x as u8,
+                propagate_singles: false,
+            }),
+            Arc::new(Backtracking::default()),
+        ]);
+        let solutions = pool
+            .solve(&mut unsolvable.clone(), |_| -> bool { panic!() })
+            .unwrap();
+        assert!(solutions.accepted.is_none());
+
+        //Gives up without searching, which says nothing about the problem
+        struct GiveUp;
+        impl SolverBackend for GiveUp {
+            fn solve(
+                &self,
+                _sudoku: &mut Sudoku,
+                _allowed_candidates: &[CandidateSet; 81],
+                _callback: &dyn Fn(&Sudoku) -> bool,
+                _is_cancelled: &dyn Fn() -> bool,
+                _check_interval: u32,
+            ) {
+            }
+        }
+        pool.set_portfolio(vec![Arc::new(DynamicBacktracking), Arc::new(GiveUp)]);
+        let calls = AtomicUsize::new(0);
+        let solutions = pool
+            .solve(&mut Sudoku::from("0"), |_| {
+                //Gives the helper thread time to give up before the calling thread is done
+                if calls.fetch_add(1, Ordering::Relaxed) == 0 {
+                    thread::sleep(Duration::from_millis(20));
+                }
+                calls.load(Ordering::Relaxed) >= 100
+            })
+            .unwrap();
+        assert!(solutions.accepted.is_some());
+
+        //The calling thread giving up must not end the solve while the helper thread searches, nor
+        //when there is no helper thread at all
+        let puzzle: Sudoku =
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003"
+                .into();
+        pool.set_portfolio(vec![Arc::new(GiveUp), Arc::new(dlx::DancingLinks)]);
+        for _ in 0..20 {
+            let solutions = pool.solve(&mut puzzle.clone(), |_| true).unwrap();
+            assert!(solutions.accepted.is_some());
+        }
+        let pool = SolverPool::builder().threads(1).build().unwrap();
+        pool.set_portfolio(vec![Arc::new(GiveUp)]);
+        let solutions = pool.solve(&mut puzzle.clone(), |_| true).unwrap();
+        assert!(solutions.accepted.is_some());
+        let solutions = pool
+            .solve(&mut unsolvable.clone(), |_| -> bool { panic!() })
+            .unwrap();
+        assert!(solutions.accepted.is_none());
+    }
+
+    #[test]
+    fn test_solver_pool_builder() {
+        init();
+        //Records the thread it runs on, then holds the problem until every thread has seen it
+        struct RecordThread(Arc<Mutex<Vec<String>>>);
+        impl SolverBackend for RecordThread {
+            fn solve(
+                &self,
+                _sudoku: &mut Sudoku,
+                _allowed_candidates: &[CandidateSet; 81],
+                _callback: &dyn Fn(&Sudoku) -> bool,
+                _is_cancelled: &dyn Fn() -> bool,
+                _check_interval: u32,
+            ) {
+                let name = thread::current().name().unwrap_or("").to_string();
+                self.0.lock().unwrap().push(name);
+                let start = Instant::now();
+                while self.0.lock().unwrap().len() < 3 && start.elapsed() < Duration::from_secs(10)
+                {
+                    thread::yield_now();
+                }
+            }
+        }
+
+        //Some CPU the test process may run on, CPU 0 can be outside a restricted affinity mask
+        #[cfg(target_os = "linux")]
+        fn allowed_cpu() -> Option<usize> {
+            unsafe extern "C" {
+                fn sched_getaffinity(pid: i32, cpusetsize: usize, mask: *mut u64) -> i32;
+            }
+            let mut mask = [0_u64; 16];
+            //SAFETY: pid 0 is the calling thread and the mask outlives the call
+            if unsafe { sched_getaffinity(0, size_of_val(&mask), mask.as_mut_ptr()) } != 0 {
+                return None;
+            }
+            (0..64 * mask.len()).find(|cpu| mask[cpu / 64] & (1 << (cpu % 64)) != 0)
+        }
+        #[cfg(not(target_os = "linux"))]
+        fn allowed_cpu() -> Option<usize> {
+            None
+        }
+
+        let names = Arc::new(Mutex::new(Vec::new()));
+        let mut builder = SolverPool::builder().threads(3).thread_name("test-pool");
+        if let Some(cpu) = allowed_cpu() {
+            builder = builder.pin_to_cpus(vec![cpu]);
+        }
+        let pool = builder.build().unwrap();
+        let recorder: Arc<dyn SolverBackend> = Arc::new(RecordThread(names.clone()));
+        pool.set_portfolio(vec![recorder.clone(), recorder.clone(), recorder]);
+        pool.solve(&mut Sudoku::from("0"), |_| true).unwrap();
+        let names = names.lock().unwrap().clone();
+        assert_eq!(names.len(), 3);
+        assert!(names.iter().any(|name| name == "test-pool-1"));
+        assert!(names.iter().any(|name| name == "test-pool-2"));
+        drop(pool);
+
+        assert!(
+            SolverPool::builder()
+                .pin_to_cpus(Vec::new())
+                .build()
+                .is_err()
+        );
+        assert!(
+            SolverPool::builder()
+                .threads(2)
+                .pin_to_cpus(vec![1 << 20])
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_job_queue() {
+        init();
+        let puzzle: Sudoku =
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003"
+                .into();
+        let solution: Sudoku =
+            "265184937379562148481973526654318279813297465792456381137629854548731692926845713"
+                .into();
+        let unsolvable: Sudoku =
+            "123456780000000009000000000000000000000000000000000000000000000000000000000000000"
+                .into();
+        let is_solution =
+            |outcome: JobOutcome| matches!(outcome, JobOutcome::Solved(s) if s == solution);
+
+        let pool = SolverPool::builder().threads(3).build().unwrap();
+        let handles: Vec<_> = (0..12)
+            .map(|k| {
+                let sudoku = if k % 3 == 2 {
+                    unsolvable.clone()
+                } else {
+                    puzzle.clone()
+                };
+                pool.submit(sudoku, |s| s.is_valid())
+            })
+            .collect();
+        for (k, handle) in handles.iter().enumerate() {
+            assert_eq!(handle.id(), k as u64);
+            match handle.wait() {
+                JobOutcome::NoSolution => assert_eq!(k % 3, 2),
+                outcome => assert!(k % 3 != 2 && is_solution(outcome)),
+            }
+            assert!(handle.is_finished());
+        }
+        //Rejecting every solution of the empty grid keeps a helper busy until the job is cancelled,
+        //problems passed to `solve` still get solved meanwhile
+        let endless = pool.submit(Sudoku::from("0"), |_| false);
+        let did_solve = AtomicBool::new(false);
+        pool.solve(
+            &mut puzzle.clone(),
+            callback_expecting_generic(Some(solution.clone()), &did_solve),
+        )
+        .unwrap();
+        assert!(did_solve.load(Ordering::Acquire));
+        assert!(endless.outcome().is_none());
+        endless.cancel();
+        assert!(matches!(endless.wait(), JobOutcome::Cancelled));
+        drop(pool);
+
+        //Without helper threads, waiting runs the job
+        let pool = SolverPool::builder().threads(1).build().unwrap();
+        let solved = pool.submit(puzzle.clone(), |_| true);
+        let cancelled = pool.submit(puzzle.clone(), |_| true);
+        let abandoned = pool.submit(puzzle.clone(), |_| true);
+        assert!(solved.outcome().is_none());
+        assert!(is_solution(solved.wait()));
+        cancelled.cancel();
+        assert!(matches!(cancelled.wait(), JobOutcome::Cancelled));
+        drop(pool);
+        assert!(matches!(abandoned.outcome(), Some(JobOutcome::Cancelled)));
+    }
+
+    #[test]
+    fn test_solve_limits() {
+        init();
+        let puzzle: Sudoku =
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003"
+                .into();
+        //Rejecting every solution of the empty grid never finishes on its own
+        let endless = Sudoku::from("0");
+        let node_limit = SolveLimits {
+            max_nodes: Some(1_000_000),
+            ..SolveLimits::default()
+        };
+
+        for backend in [
+            &DynamicBacktracking as &dyn SolverBackend,
+            &Backtracking::default(),
+            &dlx::DancingLinks,
+        ] {
+            let outcome = backend.solve_with_limits(
+                &mut endless.clone(),
+                &ALL_CANDIDATES,
+                &|_| false,
+                &|| false,
+                DEFAULT_CHECK_INTERVAL,
+                &node_limit,
+                &|_| {},
+            );
+            assert!(matches!(outcome, SolveOutcome::LimitReached(_)));
+            assert!(outcome.stats().nodes >= 1_000_000);
+            assert!(outcome.stats().solutions > 0);
+
+            let outcome = backend.solve_with_limits(
+                &mut endless.clone(),
+                &ALL_CANDIDATES,
+                &|_| false,
+                &|| false,
+                DEFAULT_CHECK_INTERVAL,
+                &SolveLimits::with_timeout(Duration::from_millis(20)),
+                &|_| {},
+            );
+            assert!(matches!(outcome, SolveOutcome::LimitReached(_)));
+            assert!(outcome.stats().elapsed >= Duration::from_millis(20));
+
+            let outcome = backend.solve_with_limits(
+                &mut puzzle.clone(),
+                &ALL_CANDIDATES,
+                &|_| true,
+                &|| false,
+                DEFAULT_CHECK_INTERVAL,
+                &node_limit,
+                &|_| {},
+            );
+            assert!(matches!(outcome, SolveOutcome::Finished(stats) if stats.solutions == 1));
+        }
+
+        let accept = AtomicBool::new(false);
+        let callback = |_: &Sudoku| accept.load(Ordering::Relaxed);
+        with_multithreaded_solver(|solver| {
+            solver.set_check_interval(1000);
+            let (outcome, _) = solver
+                .solve_with_limits(&mut endless.clone(), &ALL_CANDIDATES, callback, &node_limit)
+                .unwrap();
+            assert!(matches!(outcome, SolveOutcome::LimitReached(_)));
+            assert!(outcome.stats().nodes >= 1_000_000);
+            //The limits only apply to the problem they were passed with
+            accept.store(true, Ordering::Relaxed);
+            let (outcome, _) = solver
+                .solve_with_limits(
+                    &mut puzzle.clone(),
+                    &ALL_CANDIDATES,
+                    callback,
+                    &SolveLimits::default(),
+                )
+                .unwrap();
+            assert!(matches!(outcome, SolveOutcome::Finished(stats) if stats.solutions == 1));
+        });
+
+        let pool = SolverPool::builder().threads(3).build().unwrap();
+        let (outcome, _) = pool
+            .solve_with_limits(
+                &mut endless.clone(),
+                &ALL_CANDIDATES,
+                |_| false,
+                &SolveLimits::with_timeout(Duration::from_millis(20)),
+            )
+            .unwrap();
+        assert!(matches!(outcome, SolveOutcome::LimitReached(_)));
+        let job = pool.submit_with_limits(endless.clone(), &ALL_CANDIDATES, |_| false, &node_limit);
+        assert!(matches!(job.wait(), JobOutcome::LimitReached(stats) if stats.nodes >= 1_000_000));
+
+        let (outcome, _) = parallel::solve_parallel_with_limits(
+            &mut endless.clone(),
+            &ALL_CANDIDATES,
+            |_| false,
+            2,
+            &node_limit,
+            |_| {},
+        );
+        assert!(matches!(outcome, SolveOutcome::LimitReached(_)));
+        assert!(outcome.stats().nodes >= 1_000_000 && outcome.stats().solutions > 0);
+        let (outcome, _) = parallel::solve_parallel_with_limits(
+            &mut puzzle.clone(),
+            &ALL_CANDIDATES,
+            |_| true,
+            2,
+            &node_limit,
+            |_| {},
+        );
+        assert!(matches!(outcome, SolveOutcome::Finished(stats) if stats.solutions == 1));
+        let outcome = parallel::count_solutions_parallel_with_limits(
+            &endless,
+            usize::MAX,
+            2,
+            &SolveLimits::with_timeout(Duration::from_millis(20)),
+        );
+        assert!(matches!(outcome, SolveOutcome::LimitReached(_)));
+        assert!(outcome.stats().elapsed >= Duration::from_millis(20));
+
+        //Filling the grid from the back searches for ages before it gets to the first row
+        let unsolvable: Sudoku =
+            "123456780000000009000000000000000000000000000000000000000000000000000000000000000"
+                .into();
+        let backwards = Backtracking {
+            index_mapper: |x| 80 - x as u8,
+            propagate_singles: false,
+        };
+        let (outcome, result) = solve_batch_with_limits(
+            [puzzle.clone(), unsolvable.clone(), unsolvable.clone()],
+            &backwards,
+            2,
+            &node_limit,
+        );
+        assert!(matches!(outcome, SolveOutcome::LimitReached(_)));
+        assert!(outcome.stats().nodes >= 1_000_000);
+        assert!(result.solutions.len() == 3 && result.solutions[1..].iter().all(Option::is_none));
+        //Once the limits ran out no puzzle is started, however easy
+        let expired = SolveLimits {
+            max_nodes: Some(0),
+            ..SolveLimits::default()
+        };
+        let (outcome, result) =
+            solve_batch_with_limits([puzzle.clone(), puzzle.clone()], &backwards, 2, &expired);
+        assert!(matches!(outcome, SolveOutcome::LimitReached(stats) if stats.solutions == 0));
+        assert!(result.solutions.iter().all(Option::is_none));
+        let (outcome, result) =
+            solve_batch_with_limits([puzzle.clone(), puzzle], &backwards, 2, &node_limit);
+        assert!(matches!(outcome, SolveOutcome::Finished(stats) if stats.solutions == 2));
+        assert!(result.solutions.iter().all(Option::is_some));
+    }
+
+    #[test]
+    fn test_cancellation_token() {
+        init();
+        let puzzle: Sudoku =
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003"
+                .into();
+        //Rejecting every solution of the empty grid never finishes on its own
+        let endless = Sudoku::from("0");
+        let cancel_later = |token: &CancellationToken| {
+            let token = token.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                token.cancel();
+            })
+        };
+
+        let token = CancellationToken::new();
+        let canceller = cancel_later(&token);
+        let outcome = DynamicBacktracking.solve_with_limits(
+            &mut endless.clone(),
+            &ALL_CANDIDATES,
+            &|_| false,
+            &|| false,
+            DEFAULT_CHECK_INTERVAL,
+            &SolveLimits::with_cancellation(token.clone()),
+            &|_| {},
+        );
+        canceller.join().unwrap();
+        assert!(matches!(outcome, SolveOutcome::Cancelled(_)));
+        assert!(token.is_cancelled());
+        let outcome = dlx::DancingLinks.solve_with_limits(
+            &mut endless.clone(),
+            &ALL_CANDIDATES,
+            &|_| false,
+            &|| true,
+            DEFAULT_CHECK_INTERVAL,
+            &SolveLimits::default(),
+            &|_| {},
+        );
+        assert!(matches!(outcome, SolveOutcome::Cancelled(_)));
+
+        let pool = SolverPool::builder().threads(3).build().unwrap();
+        let token = CancellationToken::new();
+        let canceller = cancel_later(&token);
+        let (outcome, _) = pool
+            .solve_with_limits(
+                &mut endless.clone(),
+                &ALL_CANDIDATES,
+                |_| false,
+                &SolveLimits::with_cancellation(token),
+            )
+            .unwrap();
+        canceller.join().unwrap();
+        assert!(matches!(outcome, SolveOutcome::Cancelled(_)));
+        //A cancelled token only stops the solves it was passed to
+        let (outcome, _) = pool
+            .solve_with_limits(
+                &mut puzzle.clone(),
+                &ALL_CANDIDATES,
+                |_| true,
+                &SolveLimits::default(),
+            )
+            .unwrap();
+        assert!(matches!(outcome, SolveOutcome::Finished(_)));
+
+        let token = CancellationToken::new();
+        let jobs: Vec<_> = (0..3)
+            .map(|_| {
+                pool.submit_with_limits(
+                    endless.clone(),
+                    &ALL_CANDIDATES,
+                    |_| false,
+                    &SolveLimits::with_cancellation(token.clone()),
+                )
+            })
+            .collect();
+        cancel_later(&token).join().unwrap();
+        for job in jobs {
+            assert!(matches!(job.wait(), JobOutcome::Cancelled));
+        }
+
+        //Every worker of the parallel search stops
+        let token = CancellationToken::new();
+        let canceller = cancel_later(&token);
+        let (outcome, _) = parallel::solve_parallel_with_limits(
+            &mut endless.clone(),
+            &ALL_CANDIDATES,
+            |_| false,
+            4,
+            &SolveLimits::with_cancellation(token),
+            |_| {},
+        );
+        canceller.join().unwrap();
+        assert!(matches!(outcome, SolveOutcome::Cancelled(_)));
+        let token = CancellationToken::new();
+        token.cancel();
+        let outcome = parallel::count_solutions_parallel_with_limits(
+            &endless,
+            usize::MAX,
+            4,
+            &SolveLimits::with_cancellation(token),
+        );
+        assert!(matches!(outcome, SolveOutcome::Cancelled(_)));
+
+        //Filling the grid from the back searches for ages before it gets to the first row
+        let unsolvable: Sudoku =
+            "123456780000000009000000000000000000000000000000000000000000000000000000000000000"
+                .into();
+        let backwards = Backtracking {
+            index_mapper: |x| 80 - x as u8,
+            propagate_singles: false,
+        };
+        let token = CancellationToken::new();
+        let canceller = cancel_later(&token);
+        let (outcome, result) = solve_batch_with_limits(
+            [unsolvable.clone(), unsolvable, puzzle.clone()],
+            &backwards,
+            2,
+            &SolveLimits::with_cancellation(token),
+        );
+        canceller.join().unwrap();
+        assert!(matches!(outcome, SolveOutcome::Cancelled(_)));
+        assert!(result.solutions.len() == 3 && result.solutions[..2].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_progress() {
+        init();
+        let check_progress = |reports: &[SolveProgress]| {
+            assert!(!reports.is_empty());
+            for pair in reports.windows(2) {
+                assert!(pair[0].nodes < pair[1].nodes);
+                //Depth-first search only ever moves forward through the tree
+                assert!(pair[0].estimated_fraction <= pair[1].estimated_fraction);
+            }
+            for report in reports {
+                assert!((0.0..=1.0).contains(&report.estimated_fraction));
+                assert!(report.depth <= 81);
+            }
+        };
+        //Many solutions, rejecting all of them searches the whole tree
+        let ambiguous: Sudoku =
+            "000720030000000000106008709003091000580407200000000006840650010600143900005000402"
+                .into();
+
+        let reports = core::cell::RefCell::new(Vec::new());
+        solve_single_thread_with_progress::<false, _, _>(
+            &mut ambiguous.clone(),
+            &ALL_CANDIDATES,
+            |_| false,
+            || false,
+            |x| x as u8,
+            100,
+            |progress| reports.borrow_mut().push(*progress),
+        );
+        let reports = reports.into_inner();
+        check_progress(&reports);
+        assert!(reports.last().unwrap().estimated_fraction > 0.5);
+
+        let reports = core::cell::RefCell::new(Vec::new());
+        let checks = core::cell::Cell::new(0);
+        solve_single_thread_dynamic_with_progress(
+            &mut Sudoku::from("0"),
+            &ALL_CANDIDATES,
+            |_| false,
+            || {
+                checks.set(checks.get() + 1);
+                checks.get() > 20
+            },
+            DEFAULT_CHECK_INTERVAL,
+            |progress| reports.borrow_mut().push(*progress),
+        );
+        let reports = reports.into_inner();
+        check_progress(&reports);
+        assert_eq!(reports.len(), 20);
+
+        //The first rows of a solution leave many completions, and a large tree to search
+        let five_rows: Sudoku =
+            "265184937379562148481973526654318279813297465000000000000000000000000000000000000"
+                .into();
+        let four_rows: Sudoku =
+            "265184937379562148481973526654318279000000000000000000000000000000000000000000000"
+                .into();
+        for (backend, estimates) in [
+            (&Backtracking::default() as &dyn SolverBackend, true),
+            (&DynamicBacktracking, true),
+            (&dlx::DancingLinks, true),
+            (
+                &backjump::Backjumping {
+                    learn_nogoods: false,
+                },
+                false,
+            ),
+        ] {
+            let reports = core::cell::RefCell::new(Vec::new());
+            backend.solve_with_progress(
+                &mut five_rows.clone(),
+                &ALL_CANDIDATES,
+                &|_| false,
+                &|| false,
+                100,
+                &|progress| reports.borrow_mut().push(*progress),
+            );
+            let reports = reports.into_inner();
+            check_progress(&reports);
+            //Without an estimate of its own a backend still counts its nodes
+            assert_eq!(reports.last().unwrap().estimated_fraction > 0.5, estimates);
+        }
+
+        let reports = core::cell::RefCell::new(Vec::new());
+        DynamicBacktracking.count_solutions_with_progress(&four_rows, 20_000, &|progress| {
+            reports.borrow_mut().push(*progress)
+        });
+        check_progress(&reports.into_inner());
+
+        let reports = Mutex::new(Vec::new());
+        let solutions = AtomicUsize::new(0);
+        parallel::solve_parallel_with_progress(
+            &mut Sudoku::from("0"),
+            &ALL_CANDIDATES,
+            |_| solutions.fetch_add(1, Ordering::Relaxed) >= 300_000,
+            2,
+            |progress| reports.lock().unwrap().push(*progress),
+        );
+        check_progress(&reports.into_inner().unwrap());
+
+        let limits = SolveLimits {
+            max_nodes: Some(100_000),
+            ..SolveLimits::default()
+        };
+        //Only the calling thread reports, a helper thread could use up the node limit before it
+        //gets to its first check on a busy machine
+        let pool = SolverPool::builder().threads(1).build().unwrap();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let reports_ref = reports.clone();
+        pool.set_progress_callback(Some(Arc::new(move |progress| {
+            reports_ref.lock().unwrap().push(*progress)
+        })));
+        pool.set_check_interval(1000);
+        for portfolio in [Vec::new(), vec![Arc::new(dlx::DancingLinks) as _]] {
+            pool.set_portfolio(portfolio);
+            reports.lock().unwrap().clear();
+            pool.solve_with_limits(&mut Sudoku::from("0"), &ALL_CANDIDATES, |_| false, &limits)
+                .unwrap();
+            check_progress(&reports.lock().unwrap());
+        }
+
+        let job = pool.submit_with_limits(Sudoku::from("0"), &ALL_CANDIDATES, |_| false, &limits);
+        assert!(matches!(job.wait(), JobOutcome::LimitReached(_)));
+        assert!(job.progress().unwrap().nodes > 0);
+    }
+
+    #[test]
+    fn test_solution_actions() {
+        init();
+        //Swapping every 1 and 2 of the solution gives the only other one
+        let two_solutions: Sudoku =
+            "065084937379560048480973506654308079803097465790456380037609854548730690906845703"
+                .into();
+        let solution: Sudoku =
+            "265184937379562148481973526654318279813297465792456381137629854548731692926845713"
+                .into();
+
+        let mut calls = 0;
+        let solutions = solve_single_thread::<false, _, _>(
+            &mut two_solutions.clone(),
+            &ALL_CANDIDATES,
+            |_| {
+                calls += 1;
+                SolutionAction::Record
+            },
+            || false,
+            |x| x as u8,
+        );
+        assert_eq!(calls, 2);
+        assert_eq!(solutions.recorded.len(), 2);
+        assert!(solutions.recorded.contains(&solution));
+        assert!(solutions.accepted.is_none() && !solutions.aborted);
+
+        let mut calls = 0;
+        let solutions = solve_single_thread_dynamic(
+            &mut two_solutions.clone(),
+            &ALL_CANDIDATES,
+            |_| {
+                calls += 1;
+                match calls {
+                    1 => SolutionAction::Reject,
+                    _ => SolutionAction::Accept,
+                }
+            },
+            || false,
+        );
+        assert!(solutions.accepted.is_some() && solutions.recorded.is_empty());
+
+        let solutions = solve_single_thread::<true, _, _>(
+            &mut two_solutions.clone(),
+            &ALL_CANDIDATES,
+            |_| SolutionAction::Abort,
+            || false,
+            |x| x as u8,
+        );
+        assert!(solutions.aborted && solutions.accepted.is_none());
+
+        for backend in [
+            &dlx::DancingLinks as &dyn SolverBackend,
+            &Backtracking::default(),
+        ] {
+            let solutions = backend.solve_with_actions(
+                &mut two_solutions.clone(),
+                &ALL_CANDIDATES,
+                &mut |_| SolutionAction::Record,
+            );
+            assert_eq!(solutions.recorded.len(), 2);
+            assert!(solutions.recorded.contains(&solution));
+            let mut calls = 0;
+            let solutions = backend.solve_with_actions(
+                &mut two_solutions.clone(),
+                &ALL_CANDIDATES,
+                &mut |_| {
+                    calls += 1;
+                    SolutionAction::Abort
+                },
+            );
+            assert!(calls == 1 && solutions.aborted);
+        }
+
+        for threads in [1, 4] {
+            let solutions = parallel::solve_parallel(
+                &mut two_solutions.clone(),
+                &ALL_CANDIDATES,
+                |_| SolutionAction::Record,
+                threads,
+            );
+            assert_eq!(solutions.recorded.len(), 2);
+            assert!(solutions.recorded.contains(&solution));
+            let solutions = parallel::solve_parallel(
+                &mut two_solutions.clone(),
+                &ALL_CANDIDATES,
+                |s| {
+                    if *s == solution {
+                        SolutionAction::Accept
+                    } else {
+                        SolutionAction::Reject
+                    }
+                },
+                threads,
+            );
+            assert!(solutions.accepted.is_some_and(|s| s == solution));
+            assert!(solutions.recorded.is_empty() && !solutions.aborted);
+        }
+
+        with_multithreaded_solver(|solver| {
+            let solutions = solver
+                .solve(&mut two_solutions.clone(), |_| SolutionAction::Record)
+                .unwrap();
+            assert_eq!(solutions.recorded.len(), 2);
+            assert!(solutions.recorded.contains(&solution));
+        });
+
+        let pool = SolverPool::builder().threads(3).build().unwrap();
+        //Every thread finds both solutions, but each one is only handed over once. Waiting on the
+        //first one lets the other threads catch up with it.
+        let mut handed_over = Vec::new();
+        let solutions = pool
+            .solve(&mut two_solutions.clone(), |s| {
+                if handed_over.is_empty() {
+                    thread::sleep(Duration::from_millis(20));
+                }
+                handed_over.push(s.clone());
+                SolutionAction::Record
+            })
+            .unwrap();
+        assert_eq!(handed_over.len(), 2);
+        assert_eq!(solutions.recorded.len(), 2);
+        let solutions = pool
+            .solve(&mut two_solutions.clone(), |_| SolutionAction::Abort)
+            .unwrap();
+        assert!(solutions.aborted && solutions.recorded.is_empty());
+        let solutions = pool
+            .solve(&mut two_solutions.clone(), |s| {
+                if *s == solution {
+                    SolutionAction::Accept
+                } else {
+                    SolutionAction::Record
+                }
+            })
+            .unwrap();
+        assert!(solutions.accepted.is_some_and(|s| s == solution));
+        assert!(solutions.recorded.len() <= 1);
+        let job = pool.submit(two_solutions.clone(), |_| SolutionAction::Record);
+        assert!(matches!(job.wait(), JobOutcome::NoSolution));
+        assert_eq!(job.recorded().len(), 2);
+    }
+
+    #[test]
+    fn test_callback_panic() {
+        init();
+        let puzzle: Sudoku =
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003"
+                .into();
+
+        let panicking = AtomicBool::new(true);
+        let callback = |_: &Sudoku| {
+            assert!(!panicking.load(Ordering::Relaxed), "callback failed");
+            true
+        };
+        with_multithreaded_solver(|solver| {
+            panicking.store(true, Ordering::Relaxed);
+            let error = solver.solve(&mut puzzle.clone(), callback).err().unwrap();
+            assert_eq!(error.message, "callback failed");
+            panicking.store(false, Ordering::Relaxed);
+            let solutions = solver.solve(&mut puzzle.clone(), callback).unwrap();
+            assert!(solutions.accepted.is_some());
+        });
+
+        //Whichever thread finds a solution first calls the callback, helpers included
+        let pool = SolverPool::builder().threads(4).build().unwrap();
+        for _ in 0..10 {
+            let error = pool
+                .solve(&mut puzzle.clone(), |_| -> bool { panic!("{}", 42) })
+                .err()
+                .unwrap();
+            assert_eq!(error.to_string(), "solution callback panicked: 42");
+            assert!(
+                pool.solve(&mut puzzle.clone(), |_| true)
+                    .unwrap()
+                    .accepted
+                    .is_some()
+            );
+        }
+        let job = pool.submit(puzzle.clone(), |_| -> bool { panic!("job failed") });
+        assert!(matches!(job.wait(), JobOutcome::Panicked(error) if error.message == "job failed"));
+        let job = pool.submit(puzzle.clone(), |_| true);
+        assert!(matches!(job.wait(), JobOutcome::Solved(_)));
+    }
+
+    #[test]
+    fn test_callback_submits_job() {
+        init();
+        let puzzle: Sudoku =
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003"
+                .into();
+        //Whichever thread calls the callback, the job gets run while the callback waits for it
+        let pool = SolverPool::builder().threads(3).build().unwrap();
+        for _ in 0..5 {
+            let solutions = pool
+                .solve(&mut puzzle.clone(), |s| {
+                    let job = pool.submit(s.clone(), |_| true);
+                    matches!(job.wait(), JobOutcome::Solved(solution) if solution == *s)
+                })
+                .unwrap();
+            assert!(solutions.accepted.is_some());
+        }
+    }
+
+    #[test]
+    fn test_backend_panic() {
+        init();
+        struct Explode;
+        impl SolverBackend for Explode {
+            fn solve(
+                &self,
+                _sudoku: &mut Sudoku,
+                _allowed_candidates: &[CandidateSet; 81],
+                _callback: &dyn Fn(&Sudoku) -> bool,
+                _is_cancelled: &dyn Fn() -> bool,
+                _check_interval: u32,
+            ) {
+                panic!("backend failed");
+            }
+        }
+        //Never finds anything, only the other thread can end the search
+        struct Wait;
+        impl SolverBackend for Wait {
+            fn solve(
+                &self,
+                _sudoku: &mut Sudoku,
+                _allowed_candidates: &[CandidateSet; 81],
+                _callback: &dyn Fn(&Sudoku) -> bool,
+                is_cancelled: &dyn Fn() -> bool,
+                _check_interval: u32,
+            ) {
+                while !is_cancelled() {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+        }
+        let puzzle: Sudoku =
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003"
+                .into();
+
+        let pool = SolverPool::builder().threads(2).build().unwrap();
+        pool.set_portfolio(vec![Arc::new(Explode), Arc::new(Explode)]);
+        let job = pool.submit(puzzle.clone(), |_| true);
+        assert!(matches!(
+            job.wait(),
+            JobOutcome::Panicked(error) if error.source == PanicSource::Backend && error.message == "backend failed"
+        ));
+
+        //Only the helper thread panics, so the search ends only if the panic is caught there. The
+        //helper thread has to survive each time for the next solve to end.
+        pool.set_portfolio(vec![Arc::new(Wait), Arc::new(Explode)]);
+        for _ in 0..3 {
+            let error = pool.solve(&mut puzzle.clone(), |_| true).err().unwrap();
+            assert_eq!(error.to_string(), "solver backend panicked: backend failed");
+        }
+
+        pool.set_portfolio(Vec::new());
+        assert!(
+            pool.solve(&mut puzzle.clone(), |_| true)
+                .unwrap()
+                .accepted
+                .is_some()
+        );
+        let job = pool.submit(puzzle.clone(), |_| true);
+        assert!(matches!(job.wait(), JobOutcome::Solved(_)));
+
+        //The progress callback is called from inside the backend, which must not be blamed for it
+        pool.set_check_interval(100);
+        pool.set_progress_callback(Some(Arc::new(|_| panic!("progress failed"))));
+        for portfolio in [Vec::new(), vec![Arc::new(DynamicBacktracking) as _]] {
+            pool.set_portfolio(portfolio);
+            let error = pool.solve(&mut Sudoku::from("0"), |_| false).err().unwrap();
+            assert_eq!(error.source, PanicSource::ProgressCallback);
+            assert_eq!(
+                error.to_string(),
+                "progress callback panicked: progress failed"
+            );
+        }
+        pool.set_progress_callback(None);
+        assert!(
+            pool.solve(&mut puzzle.clone(), |_| true)
+                .unwrap()
+                .accepted
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_dynamic_ordering() {
+        for (puzzle, solution) in [
+            (
+                "000720030007006820106008709003091000580407200000000006840650010600143900005000402",
+                "958724631437916825126538749763291584581467293294385176849652317672143958315879462",
+            ),
+            (
+                "001000000005000000900000200000040000000057000000310402040500630600400805009000000",
+                "261794358385621947974835216138246579426957183597318462742589631613472895859163724",
+            ),
+            (
+                "009000132060000000000800005100000008000100073000600000201000000500010000900060400",
+                "879456132465321789312897645154732968698145273723689514241973856586214397937568421",
+            ),
+        ] {
+            let did_solve = AtomicBool::new(false);
+            solve_single_thread_dynamic(
+                &mut puzzle.into(),
+                &ALL_CANDIDATES,
+                callback_expecting_generic(Some(solution.into()), &did_solve),
+                || false,
+            );
+            assert!(did_solve.load(Ordering::Acquire));
+        }
+    }
+
+    // The puzzle files are not checked in, so the tests and benchmarks reading them are skipped
+    // without them
+    fn test_files_missing() -> bool {
+        let missing = !std::path::Path::new("../test_files").is_dir();
+        if missing {
+            println!("Skipping, ../test_files is missing");
+        }
+        missing
+    }
+
+    fn read_test_file_puzzles() -> Option<Vec<Sudoku>> {
+        if test_files_missing() {
+            return None;
+        }
+        let mut puzzles = Vec::new();
+        let paths = fs::read_dir("../test_files").unwrap();
+        let mut paths = paths.flatten().collect::<std::vec::Vec<_>>();
+        paths.sort_by_key(|x| (x.path().as_os_str().len(), x.path()));
+        for path in paths {
+            let reader = BufReader::new(fs::File::open(path.path()).unwrap());
+            //Skip the header line: PUZZLES,SOLUTIONS,char_count
+            for line in reader.lines().skip(1).map_while(Result::ok) {
+                puzzles.push(line[0..81].into());
+            }
+        }
+        Some(puzzles)
+    }
+
+    fn time_solver(name: &str, puzzles: &[Sudoku], solve: impl Fn(&mut Sudoku, &AtomicBool)) {
+        let did_solve = AtomicBool::new(false);
+        let solver_start = Instant::now();
+        for puzzle in puzzles {
+            did_solve.store(false, Ordering::Release);
+            solve(&mut puzzle.clone(), &did_solve);
+            assert!(did_solve.load(Ordering::Acquire));
+        }
+        println!(
+            "{}: solved {} sudokus in {:?}",
+            name,
+            puzzles.len(),
+            solver_start.elapsed()
+        );
+    }
+
+    // Compares the fixed cell orderings against picking the cell with the fewest candidates
+    // Run with `cargo test --release bench_cell_ordering -- --ignored --no-capture`
+    #[test]
+    #[ignore]
+    fn bench_cell_ordering() {
+        let Some(puzzles) = read_test_file_puzzles() else {
+            return;
+        };
+        for ordering in CELL_ORDERINGS {
+            time_solver(ordering.name, &puzzles, |sudoku, did_solve| {
+                solve_single_thread::<false, _, _>(
+                    sudoku,
+                    &ALL_CANDIDATES,
+                    callback_expecting_generic(None, did_solve),
+                    || false,
+                    |x| ordering.cells[x],
+                );
+            });
+        }
+        time_solver("fewest candidates first", &puzzles, |sudoku, did_solve| {
+            solve_single_thread_dynamic(
+                sudoku,
+                &ALL_CANDIDATES,
+                callback_expecting_generic(None, did_solve),
+                || false,
+            );
+        });
+    }
+
+    // Run with `cargo test --release bench_singles_propagation -- --ignored --no-capture`
+    #[test]
+    #[ignore]
+    fn bench_singles_propagation() {
+        let Some(puzzles) = read_test_file_puzzles() else {
+            return;
+        };
+        time_solver("row-wise", &puzzles, |sudoku, did_solve| {
+            solve_single_thread::<false, _, _>(
+                sudoku,
+                &ALL_CANDIDATES,
+                callback_expecting_generic(None, did_solve),
+                || false,
+                |x| x as u8,
+            );
+        });
+        time_solver("row-wise with singles", &puzzles, |sudoku, did_solve| {
+            solve_single_thread::<true, _, _>(
+                sudoku,
+                &ALL_CANDIDATES,
+                callback_expecting_generic(None, did_solve),
+                || false,
+                |x| x as u8,
+            );
+        });
+    }
+
+    // Run with `cargo test --release bench_puzzle_cores -- --ignored --no-capture`
+    #[test]
+    #[ignore]
+    fn bench_puzzle_cores() {
+        let Some(puzzles) = read_test_file_puzzles() else {
+            return;
+        };
+        time_solver("nibble packed core", &puzzles, |sudoku, did_solve| {
+            solve_single_thread::<false, _, _>(
+                sudoku,
+                &ALL_CANDIDATES,
+                callback_expecting_generic(None, did_solve),
+                || false,
+                |x| x as u8,
+            );
+        });
+        time_solver("bitboard core", &puzzles, |sudoku, did_solve| {
+            let callback = callback_expecting_generic(None, did_solve);
+            solve_single_thread::<false, _, _>(
+                &mut bitboard::BitboardSudoku::from(&*sudoku),
+                &ALL_CANDIDATES,
+                |s| callback(&s.into()),
+                || false,
+                |x| x as u8,
+            );
+        });
+    }
+
+    // Run with `cargo test --release bench_backends -- --ignored --no-capture`
+    #[test]
+    #[ignore]
+    fn bench_backends() {
+        let Some(puzzles) = read_test_file_puzzles() else {
+            return;
+        };
+        let backends: [(&str, Box<dyn SolverBackend>); 5] = [
+            ("backtracking", Box::new(Backtracking::default())),
+            ("fewest candidates first", Box::new(DynamicBacktracking)),
+            ("exact cover", Box::new(dlx::DancingLinks)),
+            (
+                "backjumping",
+                Box::new(backjump::Backjumping {
+                    learn_nogoods: false,
+                }),
+            ),
+            (
+                "backjumping with nogoods",
+                Box::new(backjump::Backjumping {
+                    learn_nogoods: true,
+                }),
+            ),
+        ];
+        for (name, backend) in backends {
+            time_solver(name, &puzzles, |sudoku, did_solve| {
+                backend.solve(
+                    sudoku,
+                    &ALL_CANDIDATES,
+                    &callback_expecting_generic(None, did_solve),
+                    &|| false,
+                    DEFAULT_CHECK_INTERVAL,
+                )
+            });
+        }
+    }
+
+    #[test]
+    fn test_singles_propagation() {
+        for (puzzle, solution) in [
+            (
+                "000720030007006820106008709003091000580407200000000006840650010600143900005000402",
+                "958724631437916825126538749763291584581467293294385176849652317672143958315879462",
+            ),
+            (
+                "900724030030050784100083000093400006001208009000900370016000040304860020200040000",
+                "958724631632159784147683592893475216761238459425916378516392847374861925289547163",
+            ),
+            (
+                "000080930379500040000073500004300070810090000700406001107609854040700000926000003",
+                "265184937379562148481973526654318279813297465792456381137629854548731692926845713",
+            ),
+        ] {
+            for index_mapper in [|x| x as u8, |x| (80 - x) as u8] {
+                let did_solve = AtomicBool::new(false);
+                solve_single_thread::<true, _, _>(
+                    &mut puzzle.into(),
+                    &ALL_CANDIDATES,
+                    callback_expecting_generic(Some(solution.into()), &did_solve),
+                    || false,
+                    index_mapper,
+                );
+                assert!(did_solve.load(Ordering::Acquire));
+            }
+        }
+
+        // Every solution of a grid with many of them is still visited exactly once
+        let solutions = std::sync::atomic::AtomicUsize::new(0);
+        let mut sudoku: Sudoku =
+            "000000030000006820106008709003091000580407200000000006840650010600143900005000402"
+                .into();
+        let count_solutions = |s: &Sudoku| {
+            assert!(s.is_valid());
+            solutions.fetch_add(1, Ordering::Relaxed);
+            false
+        };
+        solve_single_thread::<false, _, _>(
+            &mut sudoku,
+            &ALL_CANDIDATES,
+            count_solutions,
+            || false,
+            |x| x as u8,
+        );
+        let expected = solutions.swap(0, Ordering::Relaxed);
+        assert!(expected > 1);
+        solve_single_thread::<true, _, _>(
+            &mut sudoku,
+            &ALL_CANDIDATES,
+            count_solutions,
+            || false,
+            |x| x as u8,
+        );
+        assert_eq!(solutions.load(Ordering::Relaxed), expected);
+        assert!(sudoku.is_missing(0));
+    }
+
+    #[test]
+    fn test_batch() {
+        let puzzles = [
+            (
+                "000720030007006820106008709003091000580407200000000006840650010600143900005000402",
+                Some(
+                    "958724631437916825126538749763291584581467293294385176849652317672143958315879462",
+                ),
+            ),
+            (
+                "900724030030050784100083000093400006001208009000900370016000040304860020200040000",
+                Some(
+                    "958724631632159784147683592893475216761238459425916378516392847374861925289547163",
+                ),
+            ),
+            //No digit left for the last cell of the first row
+            (
+                "123456780000000009000000000000000000000000000000000000000000000000000000000000000",
+                None,
+            ),
+            (
+                "000080930379500040000073500004300070810090000700406001107609854040700000926000003",
+                Some(
+                    "265184937379562148481973526654318279813297465792456381137629854548731692926845713",
+                ),
+            ),
+        ];
+        let result = solve_batch(
+            puzzles.iter().map(|(puzzle, _)| Sudoku::from(*puzzle)),
+            &DynamicBacktracking,
+            3,
+        );
+        assert_eq!(result.solutions.len(), puzzles.len());
+        for ((_, expected), solution) in puzzles.iter().zip(&result.solutions) {
+            assert!(*solution == expected.map(Sudoku::from));
+        }
+        assert!(result.puzzles_per_second() > 0.0);
+    }
+
+    // Run with `cargo test --release bench_batch -- --ignored --no-capture`
+    #[test]
+    #[ignore]
+    fn bench_batch() {
+        init();
+        let Some(puzzles) = read_test_file_puzzles() else {
+            return;
+        };
+        let threads = thread::available_parallelism().unwrap().get();
+        let result = solve_batch(puzzles.iter().cloned(), &DynamicBacktracking, threads);
+        assert!(
+            result
+                .solutions
+                .iter()
+                .all(|s| s.as_ref().is_some_and(|s| s.is_valid()))
+        );
+        println!(
+            "batch: solved {} sudokus in {:?} ({:.0} / s)",
+            puzzles.len(),
+            result.elapsed,
+            result.puzzles_per_second()
+        );
+
+        let did_solve = AtomicBool::new(false);
+        let racing_start = Instant::now();
+        with_multithreaded_solver(|solver| {
+            for puzzle in &puzzles {
+                did_solve.store(false, Ordering::Release);
+                solver
+                    .solve(
+                        &mut puzzle.clone(),
+                        callback_expecting_generic(None, &did_solve),
+                    )
+                    .unwrap();
+                assert!(did_solve.load(Ordering::Acquire));
+            }
+        });
+        println!(
+            "racing threads: solved {} sudokus in {:?}",
+            puzzles.len(),
+            racing_start.elapsed()
+        );
+    }
+
+    // Splitting the search tree against one thread per puzzle order
+    // Run with `cargo test --release bench_parallel_search -- --ignored --no-capture`
+    #[test]
+    #[ignore]
+    fn bench_parallel_search() {
+        init();
+        let Some(puzzles) = read_test_file_puzzles() else {
+            return;
+        };
+        time_solver("single thread", &puzzles, |sudoku, did_solve| {
+            solve_single_thread_dynamic(
+                sudoku,
+                &ALL_CANDIDATES,
+                callback_expecting_generic(None, did_solve),
+                || false,
+            );
+        });
+        let threads = thread::available_parallelism().unwrap().get();
+        for threads in [2, 4, threads] {
+            time_solver(
+                &format!("split between {} threads", threads),
+                &puzzles,
+                |sudoku, did_solve| {
+                    parallel::solve_parallel(
+                        sudoku,
+                        &ALL_CANDIDATES,
+                        callback_expecting_generic(None, did_solve),
+                        threads,
+                    );
+                },
+            );
+        }
+    }
+
+    // Random orders without restarts are left out, a few of the sample puzzles take them minutes
+    // Run with `cargo test --release bench_random_orderings -- --ignored --no-capture`
+    #[test]
+    #[ignore]
+    fn bench_random_orderings() {
+        init();
+        let Some(puzzles) = read_test_file_puzzles() else {
+            return;
+        };
+        let did_solve = AtomicBool::new(false);
+        for (name, random_orderings) in [
+            ("fixed orderings", None),
+            (
+                "random orderings with restarts",
+                Some(RandomOrderings {
+                    seed: DEFAULT_ORDERING_SEED,
+                    restart_base: Some(DEFAULT_CHECK_INTERVAL as u64),
+                }),
+            ),
+        ] {
+            let solver_start = Instant::now();
+            with_multithreaded_solver(|solver| {
+                solver.set_random_orderings(random_orderings);
+                for puzzle in &puzzles {
+                    did_solve.store(false, Ordering::Release);
+                    solver
+                        .solve(
+                            &mut puzzle.clone(),
+                            callback_expecting_generic(None, &did_solve),
+                        )
+                        .unwrap();
+                    assert!(did_solve.load(Ordering::Acquire));
+                }
+            });
+            println!(
+                "{}: solved {} sudokus in {:?}",
+                name,
+                puzzles.len(),
+                solver_start.elapsed()
+            );
+        }
+    }
+
+    // Sixteen u16 lanes only fill a vector register with AVX2, build with `RUSTFLAGS="-Ctarget-cpu=native"`
+    // Run with `cargo test --release bench_simd -- --ignored --no-capture`
+    #[test]
+    #[ignore]
+    fn bench_simd() {
+        let Some(puzzles) = read_test_file_puzzles() else {
+            return;
+        };
+        time_solver("scalar", &puzzles, |sudoku, did_solve| {
+            solve_single_thread_dynamic(
+                sudoku,
+                &ALL_CANDIDATES,
+                callback_expecting_generic(None, did_solve),
+                || false,
+            );
+        });
+
+        let simd_start = Instant::now();
+        let solutions = simd::solve_simd(&puzzles);
+        assert!(
+            solutions
+                .iter()
+                .all(|s| s.as_ref().is_some_and(|s| s.is_valid()))
+        );
+        println!(
+            "{} lanes: solved {} sudokus in {:?}",
+            simd::LANES,
+            puzzles.len(),
+            simd_start.elapsed()
+        );
+    }
+
+    #[test]
+    fn test_files() {
+        init();
+        if test_files_missing() {
+            return;
+        }
+        let did_solve = AtomicBool::new(false);
+        with_multithreaded_solver(|solver| {
+            let mut total_time_spent_solving = Duration::new(0, 0);
+            let mut total_sudokus = 0;
+            let paths = fs::read_dir("../test_files").unwrap();
+            let mut paths = paths.flatten().collect::<std::vec::Vec<_>>();
+            paths.sort_by_key(|x| (x.path().as_os_str().len(), x.path()));
+            for path in paths {
+                let path = path.path();
+                let path_str: String = path.display().to_string();
+                print!("Solving file {}...", path_str);
+                let test_start = std::time::Instant::now();
+                let file = fs::File::open(path).unwrap();
+                let mut reader = BufReader::new(file);
+                let mut buf = String::with_capacity(81 * 2 + 1);
+
+                //Discard first line with headers: PUZZLES,SOLUTIONS,char_count
+                _ = reader.read_line(&mut buf);
+
+                loop {
+                    buf.clear();
+                    match reader.read_line(&mut buf) {
+                        Ok(0) => break,
+                        Err(_) => break,
+                        //This checks that the solver is able to arrive at the solution specified in the CSV file
+                        // _ => test_helper_with_answer!(solver, did_solve, &buf[0..81], &buf[82..163]),
+                        //This checks that the solver is able to arrive at _some_ (any) solution
+                        _ => {
+                            total_sudokus += 1;
+                            timed_test_helper!(solver, did_solve, &buf[0..81], total_time_spent_solving);
+                        },
+                    }
+                }
+                println!(
+                    "\rFinished solving file {} in {:?}",
+                    path_str,
+                    test_start.elapsed()
+                );
+            }
+            println!("Solved {} sudokus in {:?} ({:?} / sudoku)", total_sudokus, total_time_spent_solving, total_time_spent_solving/total_sudokus);
+        });
+    }
+}
@@ -0,0 +1,414 @@
+// Human-style solving: apply named techniques up to a chosen level and report where the puzzle gets stuck.
+// Useful for puzzle setters, the result tells which technique a puzzle actually requires.
+
+use crate::pencil::{PencilGrid, UNITS, units_of};
+use crate::{CandidateSet, CandidateSetIterator, Sudoku};
+
+// Iterates over the set bits of a 9 bit mask, yielding 0 based positions
+fn positions(mask: u16) -> impl Iterator<Item = usize> {
+    CandidateSetIterator(mask).map(|x| x as usize - 1)
+}
+
+/// Techniques ordered from easiest to hardest, a level is the hardest technique the solver may use
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Technique {
+    HiddenSingle,
+    NakedSingle,
+    LockedCandidates,
+    NakedPair,
+    HiddenPair,
+    NakedTriple,
+    HiddenTriple,
+    XWing,
+}
+
+impl Technique {
+    pub const ALL: [Technique; 8] = [
+        Technique::HiddenSingle,
+        Technique::NakedSingle,
+        Technique::LockedCandidates,
+        Technique::NakedPair,
+        Technique::HiddenPair,
+        Technique::NakedTriple,
+        Technique::HiddenTriple,
+        Technique::XWing,
+    ];
+
+    fn find(&self, candidates: &[CandidateSet; 81]) -> Option<Step> {
+        match self {
+            Technique::HiddenSingle => find_hidden_single(candidates),
+            Technique::NakedSingle => find_naked_single(candidates),
+            Technique::LockedCandidates => find_locked_candidates(candidates),
+            Technique::NakedPair => find_naked_subset(candidates, 2, *self),
+            Technique::HiddenPair => find_hidden_subset(candidates, 2, *self),
+            Technique::NakedTriple => find_naked_subset(candidates, 3, *self),
+            Technique::HiddenTriple => find_hidden_subset(candidates, 3, *self),
+            Technique::XWing => find_x_wing(candidates),
+        }
+    }
+}
+
+impl core::fmt::Display for Technique {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Technique::HiddenSingle => "Hidden Single",
+            Technique::NakedSingle => "Naked Single",
+            Technique::LockedCandidates => "Locked Candidates",
+            Technique::NakedPair => "Naked Pair",
+            Technique::HiddenPair => "Hidden Pair",
+            Technique::NakedTriple => "Naked Triple",
+            Technique::HiddenTriple => "Hidden Triple",
+            Technique::XWing => "X-Wing",
+        }
+        .fmt(f)
+    }
+}
+
+/// One application of a technique, placements and eliminations are `(index, digit)` pairs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub technique: Technique,
+    pub placements: Vec<(u8, u8)>,
+    pub eliminations: Vec<(u8, u8)>,
+}
+
+impl core::fmt::Display for Step {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}:", self.technique)?;
+        for (index, digit) in &self.placements {
+            write!(f, " r{}c{}={}", index / 9 + 1, index % 9 + 1, digit)?;
+        }
+        for (index, digit) in &self.eliminations {
+            write!(f, " r{}c{}<>{}", index / 9 + 1, index % 9 + 1, digit)?;
+        }
+        Ok(())
+    }
+}
+
+/// The state a step limited solve ended in
+pub struct LogicalSolve {
+    pub grid: PencilGrid,
+    pub steps: Vec<Step>,
+}
+
+impl LogicalSolve {
+    pub fn is_solved(&self) -> bool {
+        self.grid.is_solved()
+    }
+
+    pub fn hardest_technique(&self) -> Option<Technique> {
+        self.steps.iter().map(|step| step.technique).max()
+    }
+
+    fn apply(&mut self, step: &Step) {
+        for &(index, digit) in &step.placements {
+            self.grid.place(index, digit);
+        }
+        for &(index, digit) in &step.eliminations {
+            self.grid.eliminate(index, digit);
+        }
+    }
+}
+
+/// Solves using only techniques up to and including `max_technique`, always retrying the easiest
+/// technique first after every step. Stops when no allowed technique makes progress.
+pub fn solve_with_techniques(sudoku: &Sudoku, max_technique: Technique) -> LogicalSolve {
+    let mut state = LogicalSolve {
+        grid: sudoku.into(),
+        steps: Vec::new(),
+    };
+
+    'progress: loop {
+        for technique in Technique::ALL
+            .into_iter()
+            .take_while(|technique| *technique <= max_technique)
+        {
+            if let Some(step) = technique.find(&state.grid.candidates) {
+                state.apply(&step);
+                state.steps.push(step);
+                continue 'progress;
+            }
+        }
+        break;
+    }
+    state
+}
+
+// Bit `k` is set when the kth cell of the unit still has `digit` as a candidate
+fn digit_positions(candidates: &[CandidateSet; 81], unit: &[u8; 9], digit: u8) -> u16 {
+    let mut mask = 0;
+    for (k, index) in unit.iter().enumerate() {
+        if candidates[*index as usize].contains(digit) {
+            mask |= 1 << k;
+        }
+    }
+    mask
+}
+
+fn find_hidden_single(candidates: &[CandidateSet; 81]) -> Option<Step> {
+    for unit in UNITS {
+        for digit in 1..=9 {
+            let mask = digit_positions(candidates, &unit, digit);
+            if mask.count_ones() == 1 {
+                return Some(Step {
+                    technique: Technique::HiddenSingle,
+                    placements: vec![(unit[mask.trailing_zeros() as usize], digit)],
+                    eliminations: Vec::new(),
+                });
+            }
+        }
+    }
+    None
+}
+
+fn find_naked_single(candidates: &[CandidateSet; 81]) -> Option<Step> {
+    (0..81)
+        .find(|index| candidates[*index as usize].len() == 1)
+        .map(|index| Step {
+            technique: Technique::NakedSingle,
+            placements: vec![(
+                index,
+                candidates[index as usize].into_iter().next().unwrap(),
+            )],
+            eliminations: Vec::new(),
+        })
+}
+
+fn find_locked_candidates(candidates: &[CandidateSet; 81]) -> Option<Step> {
+    for digit in 1..=9 {
+        // Pointing: the digit is confined to one row or column of a subgrid
+        for (subgrid, subgrid_cells) in UNITS.iter().enumerate().skip(18) {
+            let cells: Vec<u8> = positions(digit_positions(candidates, subgrid_cells, digit))
+                .map(|k| subgrid_cells[k])
+                .collect();
+            if cells.is_empty() {
+                continue;
+            }
+            for line in [
+                Sudoku::row_index(cells[0]) as usize,
+                9 + Sudoku::col_index(cells[0]) as usize,
+            ] {
+                if !cells.iter().all(|cell| units_of(*cell).contains(&line)) {
+                    continue;
+                }
+                let eliminations = collect_eliminations(candidates, &UNITS[line], digit, |cell| {
+                    !units_of(cell).contains(&subgrid)
+                });
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique: Technique::LockedCandidates,
+                        placements: Vec::new(),
+                        eliminations,
+                    });
+                }
+            }
+        }
+
+        // Claiming: the digit is confined to one subgrid of a row or column
+        for (line, line_cells) in UNITS.iter().enumerate().take(18) {
+            let cells: Vec<u8> = positions(digit_positions(candidates, line_cells, digit))
+                .map(|k| line_cells[k])
+                .collect();
+            if cells.is_empty() {
+                continue;
+            }
+            let subgrid = 18 + Sudoku::grid_index(cells[0]) as usize;
+            if !cells.iter().all(|cell| units_of(*cell)[2] == subgrid) {
+                continue;
+            }
+            let eliminations = collect_eliminations(candidates, &UNITS[subgrid], digit, |cell| {
+                !units_of(cell).contains(&line)
+            });
+            if !eliminations.is_empty() {
+                return Some(Step {
+                    technique: Technique::LockedCandidates,
+                    placements: Vec::new(),
+                    eliminations,
+                });
+            }
+        }
+    }
+    None
+}
+
+fn collect_eliminations(
+    candidates: &[CandidateSet; 81],
+    cells: &[u8],
+    digit: u8,
+    filter: impl Fn(u8) -> bool,
+) -> Vec<(u8, u8)> {
+    cells
+        .iter()
+        .filter(|cell| candidates[**cell as usize].contains(digit) && filter(**cell))
+        .map(|cell| (*cell, digit))
+        .collect()
+}
+
+fn find_naked_subset(
+    candidates: &[CandidateSet; 81],
+    size: u32,
+    technique: Technique,
+) -> Option<Step> {
+    for unit in UNITS {
+        for combination in (1_u16..512).filter(|x| x.count_ones() == size) {
+            let mut union = 0;
+            if positions(combination).any(|k| {
+                union |= candidates[unit[k] as usize].0;
+                candidates[unit[k] as usize].is_empty()
+            }) || union.count_ones() != size
+            {
+                continue;
+            }
+            let mut eliminations = Vec::new();
+            for (k, index) in unit.into_iter().enumerate() {
+                if combination & (1 << k) == 0 {
+                    for digit in candidates[index as usize] & CandidateSet(union) {
+                        eliminations.push((index, digit));
+                    }
+                }
+            }
+            if !eliminations.is_empty() {
+                return Some(Step {
+                    technique,
+                    placements: Vec::new(),
+                    eliminations,
+                });
+            }
+        }
+    }
+    None
+}
+
+fn find_hidden_subset(
+    candidates: &[CandidateSet; 81],
+    size: u32,
+    technique: Technique,
+) -> Option<Step> {
+    for unit in UNITS {
+        let digit_masks: [u16; 9] =
+            core::array::from_fn(|d| digit_positions(candidates, &unit, d as u8 + 1));
+        for combination in (1_u16..512).filter(|x| x.count_ones() == size) {
+            let mut union = 0;
+            if positions(combination).any(|d| {
+                union |= digit_masks[d];
+                digit_masks[d] == 0
+            }) || union.count_ones() != size
+            {
+                continue;
+            }
+            let mut eliminations = Vec::new();
+            for k in positions(union) {
+                let index = unit[k];
+                for digit in candidates[index as usize] {
+                    if combination & (1 << (digit - 1)) == 0 {
+                        eliminations.push((index, digit));
+                    }
+                }
+            }
+            if !eliminations.is_empty() {
+                return Some(Step {
+                    technique,
+                    placements: Vec::new(),
+                    eliminations,
+                });
+            }
+        }
+    }
+    None
+}
+
+fn find_x_wing(candidates: &[CandidateSet; 81]) -> Option<Step> {
+    for digit in 1..=9 {
+        // Base lines are rows with columns as cover lines, then the other way around
+        for (base, cover) in [(0, 9), (9, 0)] {
+            for a in 0..9 {
+                let mask = digit_positions(candidates, &UNITS[base + a], digit);
+                if mask.count_ones() != 2 {
+                    continue;
+                }
+                for b in a + 1..9 {
+                    if digit_positions(candidates, &UNITS[base + b], digit) != mask {
+                        continue;
+                    }
+                    let mut eliminations = Vec::new();
+                    for k in positions(mask) {
+                        for (line, index) in UNITS[cover + k].into_iter().enumerate() {
+                            if line != a && line != b && candidates[index as usize].contains(digit)
+                            {
+                                eliminations.push((index, digit));
+                            }
+                        }
+                    }
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            technique: Technique::XWing,
+                            placements: Vec::new(),
+                            eliminations,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLES: [(&str, &str); 4] = [
+        (
+            "000720030007006820106008709003091000580407200000000006840650010600143900005000402",
+            "958724631437916825126538749763291584581467293294385176849652317672143958315879462",
+        ),
+        (
+            "900724030030050784100083000093400006001208009000900370016000040304860020200040000",
+            "958724631632159784147683592893475216761238459425916378516392847374861925289547163",
+        ),
+        (
+            "001000000005000000900000200000040000000057000000310402040500630600400805009000000",
+            "261794358385621947974835216138246579426957183597318462742589631613472895859163724",
+        ),
+        (
+            "009000132060000000000800005100000008000100073000600000201000000500010000900060400",
+            "879456132465321789312897645154732968698145273723689514241973856586214397937568421",
+        ),
+    ];
+
+    #[test]
+    fn test_singles_solve_easy_puzzle() {
+        let (puzzle, solution) = PUZZLES[0];
+        let result = solve_with_techniques(&puzzle.into(), Technique::NakedSingle);
+        assert!(result.is_solved());
+        assert!(*result.grid.sudoku() == solution.into());
+        assert!(result.hardest_technique() <= Some(Technique::NakedSingle));
+        assert!((0..81).all(|index| result.grid.candidates(index).is_empty()));
+    }
+
+    #[test]
+    fn test_stuck_state_keeps_solution_candidates() {
+        for (puzzle, solution) in PUZZLES {
+            let solution: Sudoku = solution.into();
+            for max_technique in Technique::ALL {
+                let result = solve_with_techniques(&puzzle.into(), max_technique);
+                assert!(result.hardest_technique() <= Some(max_technique));
+                for index in 0..81 {
+                    if result.grid.is_missing(index) {
+                        assert!(result.grid.candidates(index).contains(solution.get(index)));
+                    } else {
+                        assert_eq!(result.grid.get(index), solution.get(index));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hard_puzzle_gets_stuck_on_singles() {
+        let (puzzle, _) = PUZZLES[2];
+        let result = solve_with_techniques(&puzzle.into(), Technique::NakedSingle);
+        assert!(!result.is_solved());
+        assert!(result.steps.iter().all(|step| step.eliminations.is_empty()));
+    }
+}
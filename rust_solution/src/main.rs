@@ -1,786 +1,4 @@
-#![feature(isolate_most_least_significant_one)]
-#![feature(unsafe_cell_access)]
-#![feature(thread_id_value)]
-#![feature(file_buffered)]
-
-// Run the solver on all csv files using `RUSTFLAGS="-Cprofile-use=$PWD/merged.profdata" cargo test --release -- --no-capture`
-// Print debug logs using `RUSTFLAGS='--cfg MULTITHREADING_DEBUG' cargo run --release`
-
-/*
-PGO helps, it reduced the runtime from 237s to 205s consistently
-Generate PGO using `RUSTFLAGS="-Cprofile-generate=/tmp/pgo-data" cargo test --release -- --no-capture`
-~/.rustup/toolchains/beta-aarch64-apple-darwin/lib/rustlib/aarch64-apple-darwin/bin/llvm-profdata  merge -o ./merged.profdata /tmp/pgo-data
-
-You can install llvm-profdata via: rustup component add llvm-tools-preview
-*/
-
-use core::cell::UnsafeCell;
-use core::mem::MaybeUninit;
-use std::fs::File;
-use std::io::BufWriter;
-use std::io::Write;
-use std::sync::Mutex;
-use std::sync::Once;
-use std::thread;
-use std::time::{Duration, Instant};
-
-#[allow(unexpected_cfgs)]
-const MULTITHREADING_DEBUG: bool = cfg!(MULTITHREADING_DEBUG);
-#[allow(unexpected_cfgs)]
-const SUDOKU_DEBUG: bool = cfg!(SUDOKU_DEBUG);
-
-static INIT: Once = Once::new();
-
-thread_local! {
-    static THREAD_OUT_FILE: UnsafeCell<BufWriter<File>> = UnsafeCell::new(File::create_buffered(format!("thread{}.out", thread::current().id().as_u64())).unwrap());
-}
-
-macro_rules! thread_println {
-    ($($args:tt)*) => {
-        THREAD_OUT_FILE.with(|file|
-            unsafe { writeln!(file.as_mut_unchecked(), $($args)*).unwrap() }
-        );
-    };
-}
-
-struct TrustMeBro<T> {
-    inner: UnsafeCell<MaybeUninit<T>>,
-}
-impl<T> core::ops::Deref for TrustMeBro<T> {
-    type Target = T;
-
-    fn deref(&self) -> &Self::Target {
-        unsafe { self.inner.as_ref_unchecked().assume_init_ref() }
-    }
-}
-impl<T> TrustMeBro<T> {
-    const fn uninit() -> Self {
-        TrustMeBro {
-            inner: core::cell::UnsafeCell::new(core::mem::MaybeUninit::uninit()),
-        }
-    }
-    unsafe fn set(&self, val: T) {
-        unsafe { self.inner.as_mut_unchecked().write(val) };
-    }
-}
-unsafe impl<T: Sync> Sync for TrustMeBro<T> {}
-
-static PROGRAM_START_TIME: TrustMeBro<Instant> = TrustMeBro::uninit();
-
-trait MaybeValid {
-    fn is_valid(&self) -> bool;
-}
-impl MaybeValid for [u8; 9] {
-    fn is_valid(&self) -> bool {
-        let mut occurance = [false; 9];
-        for i in self {
-            if *i < 1 {
-                return false;
-            }
-            occurance[(*i - 1) as usize] = true
-        }
-        !occurance.contains(&false)
-    }
-}
-
-#[derive(Copy, Clone)]
-struct CandidateSet(u16);
-impl CandidateSet {
-    fn new() -> Self {
-        CandidateSet(0b111111111)
-    }
-}
-
-#[derive(Copy, Clone)]
-struct CandidateSetIterator(u16);
-
-impl Iterator for CandidateSetIterator {
-    type Item = u8;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.0 == 0 {
-            None
-        } else {
-            let set_flag = self.0.isolate_least_significant_one();
-            self.0 ^= set_flag;
-            Some(set_flag.ilog2() as u8 + 1)
-        }
-    }
-}
-
-impl CandidateSetIterator {
-    fn empty() -> Self {
-        CandidateSetIterator(0)
-    }
-
-    fn fixed() -> Self {
-        CandidateSetIterator(u16::MAX)
-    }
-
-    fn is_fixed(&self) -> bool {
-        self.0 == u16::MAX
-    }
-
-    fn is_empty(&self) -> bool {
-        self.0 == 0
-    }
-}
-
-impl IntoIterator for CandidateSet {
-    type Item = u8;
-    type IntoIter = CandidateSetIterator;
-
-    fn into_iter(self) -> Self::IntoIter {
-        CandidateSetIterator(self.0)
-    }
-}
-
-impl core::ops::BitAndAssign<u16> for CandidateSet {
-    fn bitand_assign(&mut self, rhs: u16) {
-        self.0 &= rhs;
-    }
-}
-
-impl core::ops::BitOrAssign<u16> for CandidateSet {
-    fn bitor_assign(&mut self, rhs: u16) {
-        self.0 |= rhs;
-    }
-}
-
-impl core::ops::BitAnd<CandidateSet> for CandidateSet {
-    type Output = CandidateSet;
-
-    fn bitand(self, rhs: CandidateSet) -> Self::Output {
-        CandidateSet(self.0 & rhs.0)
-    }
-}
-
-#[derive(Clone)]
-pub struct Sudoku {
-    grid: [u8; 41],
-    row_candidates: [CandidateSet; 9],
-    col_candidates: [CandidateSet; 9],
-    grid_candidates: [CandidateSet; 9],
-}
-
-impl PartialEq for Sudoku {
-    fn eq(&self, other: &Self) -> bool {
-        self.grid == other.grid
-    }
-}
-impl Eq for Sudoku {}
-
-impl Sudoku {
-    fn get(&self, index: u8) -> u8 {
-        let val = self.grid[(index >> 1) as usize];
-        if index & 1 == 1 {
-            val >> 4
-        } else {
-            val & 0b1111
-        }
-    }
-
-    fn is_missing(&self, index: u8) -> bool {
-        self.get(index) == 0
-    }
-
-    fn set(&mut self, index: u8, val: u8) {
-        let oldval;
-        let oldvalpair = &mut self.grid[(index >> 1) as usize];
-        let row_candidate_set = &mut self.row_candidates[Self::row_index(index) as usize];
-        let col_candidate_set = &mut self.col_candidates[Self::col_index(index) as usize];
-        let grid_candidate_set = &mut self.grid_candidates[Self::grid_index(index) as usize];
-
-        if index & 1 == 1 {
-            oldval = *oldvalpair >> 4;
-            *oldvalpair = *oldvalpair & 0b1111 | val << 4;
-        } else {
-            oldval = *oldvalpair & 0b1111;
-            *oldvalpair = (*oldvalpair & 0b11110000) | val;
-        }
-
-        if oldval != 0 {
-            let bit_filter = 1 << (oldval - 1);
-            *row_candidate_set |= bit_filter;
-            *col_candidate_set |= bit_filter;
-            *grid_candidate_set |= bit_filter;
-        }
-        if val != 0 {
-            let bit_filter = !(1 << (val - 1));
-            *row_candidate_set &= bit_filter;
-            *col_candidate_set &= bit_filter;
-            *grid_candidate_set &= bit_filter;
-        }
-    }
-
-    /*
-    00 01 02 03 04 05 06 07 08
-    09 10 11 12 13 14 15 16 17
-    18 19 20 21 22 23 24 25 26
-    27 28 29 30 31 32 33 34 35
-    36 37 38 39 40 41 42 43 44
-    45 46 47 48 49 50 51 52 53
-    54 55 56 57 58 59 60 61 62
-    63 64 65 66 67 68 69 70 71
-    72 73 74 75 76 77 78 79 80
-
-    bash commands:
-    grid_from() { local x=$1; echo $x, $(($x+1)), $(($x+2)), $(($x+9)), $(($x+10)), $(($x+11)), $(($x+18)), $(($x+19)), $(($x+20)) ; }
-    */
-
-    fn grid_index(index: u8) -> u8 {
-        let row_num = index / 27;
-        let col_num = (index % 9) / 3;
-        row_num * 3 + col_num
-    }
-
-    fn row_index(index: u8) -> u8 {
-        index / 9
-    }
-
-    fn col_index(index: u8) -> u8 {
-        index % 9
-    }
-
-    fn get_candidates(&self, index: u8) -> CandidateSet {
-        let row_candidate_set = self.row_candidates[Self::row_index(index) as usize];
-        let col_candidate_set = self.col_candidates[Self::col_index(index) as usize];
-        let grid_candidate_set = self.grid_candidates[Self::grid_index(index) as usize];
-        col_candidate_set & row_candidate_set & grid_candidate_set
-    }
-}
-
-impl MaybeValid for Sudoku {
-    fn is_valid(&self) -> bool {
-        for i in 0..9 {
-            if ![
-                self.get(i * 9),
-                self.get(i * 9 + 1),
-                self.get(i * 9 + 2),
-                self.get(i * 9 + 3),
-                self.get(i * 9 + 4),
-                self.get(i * 9 + 5),
-                self.get(i * 9 + 6),
-                self.get(i * 9 + 7),
-                self.get(i * 9 + 8),
-            ]
-            .is_valid()
-            {
-                return false;
-            }
-
-            if ![
-                self.get(i),
-                self.get(i + 9),
-                self.get(i + 9 * 2),
-                self.get(i + 9 * 3),
-                self.get(i + 9 * 4),
-                self.get(i + 9 * 5),
-                self.get(i + 9 * 6),
-                self.get(i + 9 * 7),
-                self.get(i + 9 * 8),
-            ]
-            .is_valid()
-            {
-                return false;
-            }
-        }
-
-        for i in [0, 3, 6, 27, 30, 33, 54, 57, 60] {
-            if ![
-                self.get(i),
-                self.get(i + 1),
-                self.get(i + 2),
-                self.get(i + 9),
-                self.get(i + 10),
-                self.get(i + 11),
-                self.get(i + 18),
-                self.get(i + 19),
-                self.get(i + 20),
-            ]
-            .is_valid()
-            {
-                return false;
-            }
-        }
-
-        true
-    }
-}
-
-impl From<[u8; 81]> for Sudoku {
-    fn from(v: [u8; 81]) -> Self {
-        let mut sudoku = Sudoku {
-            grid: [0; _],
-            row_candidates: [CandidateSet::new(); _],
-            col_candidates: [CandidateSet::new(); _],
-            grid_candidates: [CandidateSet::new(); _],
-        };
-        for (k, v) in v.into_iter().enumerate() {
-            if v != 0 {
-                sudoku.set(k as u8, v);
-            }
-        }
-        sudoku
-    }
-}
-
-impl From<[u8; 41]> for Sudoku {
-    fn from(v: [u8; 41]) -> Self {
-        let mut sudoku = Sudoku {
-            grid: [0; _],
-            row_candidates: [CandidateSet::new(); _],
-            col_candidates: [CandidateSet::new(); _],
-            grid_candidates: [CandidateSet::new(); _],
-        };
-        for (k, v) in v.into_iter().enumerate() {
-            let v1 = v & 0b1111;
-            let v2 = v >> 4;
-            if v1 != 0 {
-                sudoku.set((k * 2) as u8, v1);
-            }
-            if v2 != 0 && k != 40 {
-                sudoku.set((k * 2 + 1) as u8, v2);
-            }
-        }
-        sudoku
-    }
-}
-
-impl From<&str> for Sudoku {
-    fn from(value: &str) -> Self {
-        let mut sudoku = Sudoku {
-            grid: [0; _],
-            row_candidates: [CandidateSet::new(); _],
-            col_candidates: [CandidateSet::new(); _],
-            grid_candidates: [CandidateSet::new(); _],
-        };
-        for (k, v) in value.as_bytes().iter().enumerate() {
-            let digit = *v - b'0';
-            if digit != 0_u8 {
-                sudoku.set(k as u8, digit);
-            }
-        }
-        sudoku
-    }
-}
-
-impl core::fmt::Display for Sudoku {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        for i in 0..9 {
-            "\n".fmt(f)?;
-            for j in 0..9 {
-                let val = self.get(i * 9 + j);
-                if val == 0 {
-                    "?".fmt(f)?;
-                } else {
-                    val.fmt(f)?;
-                }
-                ", ".fmt(f)?;
-            }
-        }
-        Ok(())
-    }
-}
-
-struct SingleLineDisplayAdaptor<'a, T>(&'a T);
-impl core::fmt::Display for SingleLineDisplayAdaptor<'_, Sudoku> {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        for i in 0..9 {
-            // "\n".fmt(f)?;
-            for j in 0..9 {
-                let val = self.0.get(i * 9 + j);
-                if val == 0 {
-                    "?".fmt(f)?;
-                } else {
-                    val.fmt(f)?;
-                }
-                // ", ".fmt(f)?;
-            }
-        }
-        Ok(())
-    }
-}
-
-struct SharedContext<SOLFN> {
-    current_problem_index: i32,
-    current_problem: Sudoku,
-    solution_callback: Option<SOLFN>, //None value signifies that problem is solved
-}
-
-struct Solver<'a, SOLFN> {
-    shared_context: &'a Mutex<SharedContext<SOLFN>>,
-}
-
-impl<'a, SOLFN> Solver<'a, SOLFN>
-where
-    SOLFN: Fn(&Sudoku) -> bool + std::marker::Send,
-{
-    fn solve(&mut self, sudoku: &mut Sudoku, callback: SOLFN) {
-        // unsafe { PROGRAM_START_TIME.set(Instant::now()); }
-        if MULTITHREADING_DEBUG {
-            thread_println!(
-                "{:?}: Queueing next problem with main thread: {:?}",
-                PROGRAM_START_TIME.elapsed().as_nanos(),
-                thread::current().id()
-            );
-        }
-        {
-            let mut shared_context = self.shared_context.lock().unwrap();
-            shared_context.current_problem_index += 1;
-            shared_context.current_problem = sudoku.clone();
-            shared_context.solution_callback = Some(callback);
-            if MULTITHREADING_DEBUG {
-                thread_println!(
-                    "{:?}: Queued problem {} with main thread: {:?}",
-                    PROGRAM_START_TIME.elapsed().as_nanos(),
-                    shared_context.current_problem_index,
-                    thread::current().id()
-                );
-            }
-        }
-        if MULTITHREADING_DEBUG {
-            thread_println!(
-                "{:?}: Started work on the problem in main thread: {:?}",
-                PROGRAM_START_TIME.elapsed().as_nanos(),
-                thread::current().id()
-            );
-        }
-        multithreaded_helper::<false, _>(self.shared_context, |x| x as u8);
-    }
-}
-
-fn multithreaded_helper<const STAY_ALIVE: bool, SOLFN: Fn(&Sudoku) -> bool + std::marker::Send>(
-    shared_context: &Mutex<SharedContext<SOLFN>>,
-    index_mapper: impl Fn(usize) -> u8,
-) {
-    let mut local_last_known_problem_index;
-    let mut local_last_known_problem;
-    loop {
-        if MULTITHREADING_DEBUG {
-            thread_println!(
-                "{:?}: Thread {:?} is checking for new tasks...",
-                PROGRAM_START_TIME.elapsed().as_nanos(),
-                thread::current().id()
-            );
-        }
-        {
-            let shared_context = shared_context.lock().unwrap();
-            let shared_context_is_solved = shared_context.solution_callback.is_none();
-            let shared_context_current_problem_index = shared_context.current_problem_index;
-            let shared_context_current_problem = shared_context.current_problem.clone();
-            core::mem::drop(shared_context);
-            if shared_context_current_problem_index == -1 {
-                if MULTITHREADING_DEBUG {
-                    thread_println!(
-                        "{:?}: Thread {:?} acknowledged order to shut down",
-                        PROGRAM_START_TIME.elapsed().as_nanos(),
-                        thread::current().id()
-                    );
-                }
-                break;
-            }
-            if shared_context_is_solved {
-                if MULTITHREADING_DEBUG {
-                    thread_println!(
-                        "{:?}: Thread {:?} found no new tasks",
-                        PROGRAM_START_TIME.elapsed().as_nanos(),
-                        thread::current().id()
-                    );
-                }
-                if !STAY_ALIVE {
-                    if MULTITHREADING_DEBUG {
-                        thread_println!(
-                            "{:?}: Main Thread {:?} was late to the party, yielding control to caller.",
-                            PROGRAM_START_TIME.elapsed().as_nanos(),
-                            thread::current().id()
-                        );
-                    }
-                    break;
-                } else {
-                    thread::sleep(Duration::from_micros(10));
-                    continue;
-                }
-            }
-            local_last_known_problem_index = shared_context_current_problem_index;
-            local_last_known_problem = shared_context_current_problem;
-            if MULTITHREADING_DEBUG {
-                thread_println!(
-                    "{:?}: Thread {:?} found work, will start work on {}",
-                    PROGRAM_START_TIME.elapsed().as_nanos(),
-                    thread::current().id(),
-                    local_last_known_problem_index
-                );
-            }
-        }
-
-        solve_single_thread(
-            &mut local_last_known_problem,
-            |solved_sudoku| {
-                let mut shared_context = shared_context.lock().unwrap();
-
-                match &shared_context.solution_callback {
-                    Some(callback)
-                        if shared_context.current_problem_index
-                            == local_last_known_problem_index
-                            && callback(solved_sudoku) =>
-                    {
-                        //We solved the current problem, which was unsolved
-                        //Mark it solved, and cancel the current solve calc
-                        if MULTITHREADING_DEBUG {
-                            thread_println!(
-                                "{:?}: Thread {:?} SUCCESS has solved problem {}",
-                                PROGRAM_START_TIME.elapsed().as_nanos(),
-                                thread::current().id(),
-                                local_last_known_problem_index
-                            );
-                        }
-
-                        shared_context.solution_callback = None;
-                        true
-                    }
-                    _ => {
-                        let should_stop = shared_context.solution_callback.is_none()
-                            || shared_context.current_problem_index
-                                != local_last_known_problem_index;
-                        if MULTITHREADING_DEBUG {
-                            if should_stop {
-                                thread_println!(
-                                    "{:?}: Thread {:?} CANCELLING problem {} [via on_solved]",
-                                    PROGRAM_START_TIME.elapsed().as_nanos(),
-                                    thread::current().id(),
-                                    local_last_known_problem_index
-                                );
-                            } else {
-                                thread_println!(
-                                    "{:?}: Thread {:?} CONTINUING,[via on_solved] for problem {}",
-                                    PROGRAM_START_TIME.elapsed().as_nanos(),
-                                    thread::current().id(),
-                                    local_last_known_problem_index
-                                );
-                            }
-                        }
-                        should_stop
-                    }
-                }
-            },
-            || {
-                let shared_context = shared_context.lock().unwrap();
-                let should_stop = shared_context.solution_callback.is_none()
-                    || shared_context.current_problem_index != local_last_known_problem_index;
-                if MULTITHREADING_DEBUG {
-                    if should_stop {
-                        thread_println!(
-                            "{:?}: Thread {:?} CANCELLING problem {} [via is_cancelled]",
-                            PROGRAM_START_TIME.elapsed().as_nanos(),
-                            thread::current().id(),
-                            local_last_known_problem_index
-                        );
-                    } else {
-                        thread_println!(
-                            "{:?}: Thread {:?} CONTINUING,[via is_cancelled] for problem {}",
-                            PROGRAM_START_TIME.elapsed().as_nanos(),
-                            thread::current().id(),
-                            local_last_known_problem_index
-                        );
-                    }
-                }
-                should_stop
-            },
-            &index_mapper,
-        );
-
-        if !STAY_ALIVE {
-            if MULTITHREADING_DEBUG {
-                thread_println!(
-                    "{:?}: Thread {:?} has !STAY_ALIVE, yielding control to caller.",
-                    PROGRAM_START_TIME.elapsed().as_nanos(),
-                    thread::current().id()
-                );
-            }
-            break;
-        }
-    }
-}
-
-fn with_multithreaded_solver<T, SOLFN: Fn(&Sudoku) -> bool + std::marker::Send>(
-    solving_callback: impl Fn(&mut Solver<SOLFN>) -> T,
-) -> T {
-    let mut ret_val: Option<T> = None;
-
-    let shared_context = Mutex::new(SharedContext {
-        current_problem_index: 0,
-        current_problem: Sudoku::from("0"),
-        solution_callback: None as Option<SOLFN>,
-    });
-    let mut solver = Solver {
-        shared_context: &shared_context,
-    };
-
-    thread::scope(|scope| {
-        for index_mapper in [
-            |x| (80 - x) as u8, //reverse row-wise
-            |x| {
-                [
-                    0, 1, 2, 9, 10, 11, 18, 19, 20, 27, 28, 29, 36, 37, 38, 45, 46, 47, 54, 55, 56,
-                    63, 64, 65, 72, 73, 74, 3, 4, 5, 12, 13, 14, 21, 22, 23, 30, 31, 32, 39, 40,
-                    41, 48, 49, 50, 57, 58, 59, 66, 67, 68, 75, 76, 77, 6, 7, 8, 15, 16, 17, 24,
-                    25, 26, 33, 34, 35, 42, 43, 44, 51, 52, 53, 60, 61, 62, 69, 70, 71, 78, 79, 80,
-                ][x]
-            }, //subgrid wise
-            |x| {
-                [
-                    0, 9, 18, 27, 36, 45, 54, 63, 72, 1, 10, 19, 28, 37, 46, 55, 64, 73, 2, 11, 20,
-                    29, 38, 47, 56, 65, 74, 3, 12, 21, 30, 39, 48, 57, 66, 75, 4, 13, 22, 31, 40,
-                    49, 58, 67, 76, 5, 14, 23, 32, 41, 50, 59, 68, 77, 6, 15, 24, 33, 42, 51, 60,
-                    69, 78, 7, 16, 25, 34, 43, 52, 61, 70, 79, 8, 17, 26, 35, 44, 53, 62, 71, 80,
-                ][x]
-            }, //col wise
-            |x| {
-                [
-                    0, 9, 18, 27, 36, 45, 54, 63, 72, 1, 10, 19, 28, 37, 46, 55, 64, 73, 2, 11, 20,
-                    29, 38, 47, 56, 65, 74, 3, 12, 21, 30, 39, 48, 57, 66, 75, 4, 13, 22, 31, 40,
-                    49, 58, 67, 76, 5, 14, 23, 32, 41, 50, 59, 68, 77, 6, 15, 24, 33, 42, 51, 60,
-                    69, 78, 7, 16, 25, 34, 43, 52, 61, 70, 79, 8, 17, 26, 35, 44, 53, 62, 71, 80,
-                ][80 - x]
-            }, //rev col wise
-            |x| {
-                [
-                    0, 1, 2, 9, 10, 11, 18, 19, 20, 27, 28, 29, 36, 37, 38, 45, 46, 47, 54, 55, 56,
-                    63, 64, 65, 72, 73, 74, 3, 4, 5, 12, 13, 14, 21, 22, 23, 30, 31, 32, 39, 40,
-                    41, 48, 49, 50, 57, 58, 59, 66, 67, 68, 75, 76, 77, 6, 7, 8, 15, 16, 17, 24,
-                    25, 26, 33, 34, 35, 42, 43, 44, 51, 52, 53, 60, 61, 62, 69, 70, 71, 78, 79, 80,
-                ][80 - x]
-            }, //rev subgrid wise
-            |x| {
-                [
-                    20, 66, 78, 77, 39, 68, 57, 69, 65, 74, 13, 19, 60, 38, 23, 53, 5, 6, 12, 73,
-                    59, 51, 30, 58, 80, 24, 0, 9, 42, 64, 52, 41, 61, 21, 31, 27, 17, 67, 33, 62,
-                    4, 11, 63, 48, 10, 70, 34, 2, 44, 45, 46, 1, 29, 15, 26, 16, 7, 56, 71, 35, 40,
-                    28, 37, 76, 25, 43, 79, 54, 49, 14, 50, 72, 36, 18, 55, 75, 3, 8, 47, 22, 32,
-                ][x]
-            }, //random
-            |x| {
-                [
-                    10, 38, 58, 73, 19, 52, 72, 68, 55, 31, 63, 43, 13, 65, 20, 60, 57, 5, 80, 79,
-                    16, 17, 44, 39, 4, 25, 14, 32, 23, 27, 66, 9, 24, 71, 70, 8, 61, 0, 21, 30, 56,
-                    33, 74, 59, 3, 64, 54, 69, 37, 40, 28, 2, 77, 78, 46, 42, 6, 15, 7, 76, 22, 1,
-                    35, 11, 45, 51, 36, 50, 62, 41, 49, 26, 12, 67, 29, 18, 48, 34, 47, 75, 53,
-                ][x]
-            }, //random
-            |x| {
-                [
-                    41, 79, 23, 76, 29, 35, 36, 28, 52, 34, 37, 7, 43, 38, 59, 48, 50, 62, 24, 3,
-                    14, 10, 30, 40, 5, 64, 11, 25, 31, 26, 69, 33, 61, 78, 77, 70, 1, 12, 13, 74,
-                    42, 58, 22, 16, 20, 4, 57, 27, 15, 39, 63, 49, 56, 21, 60, 51, 53, 46, 44, 72,
-                    73, 67, 68, 75, 2, 6, 32, 19, 66, 8, 17, 47, 80, 65, 0, 71, 54, 55, 9, 45, 18,
-                ][x]
-            }, //random
-        ]
-        .into_iter()
-        .take(thread::available_parallelism().unwrap().get() - 1)
-        {
-            let shared_context_ref = &shared_context;
-            scope.spawn(move || {
-                if MULTITHREADING_DEBUG {
-                    thread_println!(
-                        "{:?}: Spawned helper thread {:?}",
-                        PROGRAM_START_TIME.elapsed().as_nanos(),
-                        thread::current().id()
-                    );
-                }
-
-                multithreaded_helper::<true, _>(shared_context_ref, index_mapper);
-            });
-        }
-
-        ret_val = Some(solving_callback(&mut solver));
-
-        // Shut down the threads
-        let mut shared_context = shared_context.lock().unwrap();
-        shared_context.current_problem_index = -1;
-    });
-    ret_val.unwrap()
-}
-
-pub fn solve_single_thread(
-    sudoku: &mut Sudoku,
-    callback: impl Fn(&Sudoku) -> bool,
-    is_cancelled: impl Fn() -> bool,
-    index_mapper: impl Fn(usize) -> u8,
-) {
-    let mut stack: [CandidateSetIterator; 81] = [CandidateSetIterator::empty(); _];
-    let mut stack_idx = usize::MAX;
-    let mut counter = 0;
-    for (k, v) in stack.iter_mut().enumerate() {
-        if !sudoku.is_missing(index_mapper(k)) {
-            *v = CandidateSetIterator::fixed()
-        }
-    }
-
-    let pop_task = |sudoku: &mut Sudoku,
-                    stack: &mut [CandidateSetIterator; 81],
-                    stack_idx: &mut usize,
-                    counter: &mut i32|
-     -> bool {
-        *stack_idx = (*stack_idx).min(80);
-        while *stack_idx <= 80 && (stack[*stack_idx].is_empty() || stack[*stack_idx].is_fixed()) {
-            if !stack[*stack_idx].is_fixed() {
-                sudoku.set(index_mapper(*stack_idx), 0);
-            }
-            *stack_idx = stack_idx.wrapping_sub(1);
-        }
-        if *stack_idx > 80 {
-            //Stack is empty, no more tasks
-            false
-        } else {
-            *counter += 1;
-            if *counter > 100000 {
-                *counter = 0;
-                if is_cancelled() {
-                    return false;
-                }
-            }
-            sudoku.set(index_mapper(*stack_idx), stack[*stack_idx].next().unwrap());
-            if SUDOKU_DEBUG {
-                println!("Trying:  {}", SingleLineDisplayAdaptor(sudoku));
-                println!(
-                    "         {}^",
-                    " ".repeat(index_mapper(*stack_idx) as usize)
-                );
-            }
-            true
-        }
-    };
-    let push_tasks = |sudoku: &mut Sudoku,
-                      stack: &mut [CandidateSetIterator; 81],
-                      stack_idx: &mut usize|
-     -> bool {
-        *stack_idx = (*stack_idx).wrapping_add(1);
-        while *stack_idx <= 80 && stack[*stack_idx].is_fixed() {
-            *stack_idx += 1;
-        }
-        if *stack_idx > 80 {
-            if callback(sudoku) {
-                return false;
-            }
-        } else {
-            stack[*stack_idx] = sudoku.get_candidates(index_mapper(*stack_idx)).into_iter()
-        }
-        true
-    };
-
-    push_tasks(sudoku, &mut stack, &mut stack_idx);
-    while pop_task(sudoku, &mut stack, &mut stack_idx, &mut counter)
-        && push_tasks(sudoku, &mut stack, &mut stack_idx)
-    {}
-}
-
-fn init() {
-    INIT.call_once(|| unsafe {
-        PROGRAM_START_TIME.set(Instant::now());
-    });
-}
+use sudoku::{MaybeValid, Sudoku, init, with_multithreaded_solver};
 
 fn main() {
     init();
@@ -790,233 +8,12 @@ fn main() {
                 .into();
         assert!(!sudoku.is_valid());
         println!("Problem: {}", sudoku);
-        solver.solve(&mut sudoku, |s| {
-            assert!(s.is_valid());
-            println!("Solved:  {}", &s);
-            true
-        });
-    });
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use std::io::{BufRead, BufReader};
-    use std::sync::atomic::{AtomicBool, Ordering};
-
-    macro_rules! test_helper {
-        ($solver:ident, $did_solve:ident, $sudoku_str:expr) => {{
-            let mut sudoku: Sudoku = $sudoku_str.into();
-            $did_solve.store(false, Ordering::Release);
-            assert!(!sudoku.is_valid());
-            $solver.solve(&mut sudoku, callback_expecting_generic(None, &$did_solve));
-            assert!($did_solve.load(Ordering::Acquire));
-        }};
-    }
-
-    macro_rules! timed_test_helper {
-        ($solver:ident, $did_solve:ident, $sudoku_str:expr, $time:ident) => {{
-            let mut sudoku: Sudoku = $sudoku_str.into();
-            $did_solve.store(false, Ordering::Release);
-            assert!(!sudoku.is_valid());
-            let solver_start = Instant::now();
-            $solver.solve(&mut sudoku, callback_expecting_generic(None, &$did_solve));
-            $time += solver_start.elapsed();
-            assert!($did_solve.load(Ordering::Acquire));
-        }};
-    }
-
-    macro_rules! test_helper_with_answer {
-        ($solver:ident, $did_solve:ident, $sudoku_str:expr, $solution:expr) => {{
-            let mut sudoku: Sudoku = $sudoku_str.into();
-            $did_solve.store(false, Ordering::Release);
-            assert!(!sudoku.is_valid());
-            $solver.solve(
-                &mut sudoku,
-                callback_expecting_generic(Some($solution.into()), &$did_solve),
-            );
-            assert!($did_solve.load(Ordering::Acquire));
-        }};
-    }
-
-    fn callback_expecting_generic(
-        solution: Option<Sudoku>,
-        atomic: &AtomicBool,
-    ) -> impl Fn(&Sudoku) -> bool + Send {
-        move |candidate: &Sudoku| {
-            assert!(candidate.is_valid());
-            if match &solution {
-                None => true,
-                Some(sol) => *candidate == *sol,
-            } {
-                atomic.store(true, Ordering::Release);
+        solver
+            .solve(&mut sudoku, |s| {
+                assert!(s.is_valid());
+                println!("Solved:  {}", &s);
                 true
-            } else {
-                false
-            }
-        }
-    }
-
-    #[test]
-    fn test_basic() {
-        init();
-        let did_solve = AtomicBool::new(false);
-        let x = with_multithreaded_solver(|solver| {
-            test_helper_with_answer!(solver, did_solve, "351897264897642135642351789265139478784265913139784526918476352523918647476503891","351897264897642135642351789265139478784265913139784526918476352523918647476523891");
-            test_helper_with_answer!(solver, did_solve, "987645213132978465654123798579364182346812950821597634495236871263781549718459326","987645213132978465654123798579364182346812957821597634495236871263781549718459326");
-            test_helper_with_answer!(solver, did_solve, "537864129864912375912537648629175483175348296348629751783496512201783964496251837","537864129864912375912537648629175483175348296348629751783496512251783964496251837");
-            test_helper_with_answer!(solver, did_solve, "275396418481275369396481257813754926962813745754962801627139584139548672548627193","275396418481275369396481257813754926962813745754962831627139584139548672548627193");
-            test_helper_with_answer!(solver, did_solve, "835791426642853197719624358961248735573916842084537619458379261126485973397162584","835791426642853197719624358961248735573916842284537619458379261126485973397162584");
-            test_helper_with_answer!(solver, did_solve, "281659734659703218743281695516497823497832561832516479378125946125964387964378152","281659734659743218743281695516497823497832561832516479378125946125964387964378152");
-            test_helper_with_answer!(solver, did_solve, "849756123756213489213849576532198647198467350467532918625381794974625831381974265","849756123756213489213849576532198647198467352467532918625381794974625831381974265");
-            test_helper_with_answer!(solver, did_solve, "721645839564893271389217654693178425817452963245936187436789512978521346150364798","721645839564893271389217654693178425817452963245936187436789512978521346152364798");
-            test_helper_with_answer!(solver, did_solve, "971483526834562179625197438583216704497358261162749385258671943716934852349825617","971483526834562179625197438583216794497358261162749385258671943716934852349825617");
-            test_helper_with_answer!(solver, did_solve, "971483562526971438403526917219734856734865291865219743147358629358692174692147385","971483562526971438483526917219734856734865291865219743147358629358692174692147385");
-            test_helper_with_answer!(solver, did_solve, "824517396157936824396284157719362485485179630632845719973628541541793268268451973","824517396157936824396284157719362485485179632632845719973628541541793268268451973");
-            test_helper_with_answer!(solver, did_solve, "849756231132948756657231948284569317713482569965317482571823694328694170496175823","849756231132948756657231948284569317713482569965317482571823694328694175496175823");
-            test_helper_with_answer!(solver, did_solve, "215364987879215436364879521531486792927531648486927150752143869143698275698752314","215364987879215436364879521531486792927531648486927153752143869143698275698752314");
-            test_helper_with_answer!(solver, did_solve, "374612589612958743958374126435267018267891354891435672549723861723186495186549237","374612589612958743958374126435267918267891354891435672549723861723186495186549237");
-            test_helper_with_answer!(solver, did_solve, "127936548603485127548271693275319486486752931931864275869547312312698754754123869","127936548693485127548271693275319486486752931931864275869547312312698754754123869");
-            test_helper_with_answer!(solver, did_solve, "374598261621437895985162734198246357537819642462753918219674583746305129853921476","374598261621437895985162734198246357537819642462753918219674583746385129853921476");
-            test_helper_with_answer!(solver, did_solve, "914623758758194203263578914547319682682457139139862547876945321321786495495231876","914623758758194263263578914547319682682457139139862547876945321321786495495231876");
-            test_helper_with_answer!(solver, did_solve, "768314295143029768295876143481293657657148932932765481319652874526487319874931526","768314295143529768295876143481293657657148932932765481319652874526487319874931526");
-            test_helper_with_answer!(solver, did_solve, "914386257386257914257914386172849563560172849849563172698735421735421698421698735","914386257386257914257914386172849563563172849849563172698735421735421698421698735");
-            test_helper_with_answer!(solver, did_solve, "987643521634215897251078364542891736819367452376524189128739645465182973793456218","987643521634215897251978364542891736819367452376524189128739645465182973793456218");
-            test_helper_with_answer!(solver, did_solve, "561487392309651874847239516136574928754892163982316745693145287415728639278963451","561487392329651874847239516136574928754892163982316745693145287415728639278963451");
-            test_helper_with_answer!(solver, did_solve, "519837246264915783738462591187243659342596178695781324823654907971328465456179832","519837246264915783738462591187243659342596178695781324823654917971328465456179832");
-            test_helper_with_answer!(solver, did_solve, "281673459736549218495128763129867534543912687678354192357491826062735941914286375","281673459736549218495128763129867534543912687678354192357491826862735941914286375");
-            test_helper_with_answer!(solver, did_solve, "281673495594182673376495182815267349043518267762349518159826734628734951437951826","281673495594182673376495182815267349943518267762349518159826734628734951437951826");
-            test_helper_with_answer!(solver, did_solve, "721645983938271456465398712142956837596783124873412569254869371310524698689137245","721645983938271456465398712142956837596783124873412569254869371317524698689137245");
-            test_helper_with_answer!(solver, did_solve, "721645983938217546564389712219476358385192674647853291193720865856931427472568139","721645983938217546564389712219476358385192674647853291193724865856931427472568139");
-            test_helper_with_answer!(solver, did_solve, "000720030007006820106008709003091000580407200000000006840650010600143900005000402","958724631437916825126538749763291584581467293294385176849652317672143958315879462");
-            test_helper_with_answer!(solver, did_solve, "900724030030050784100083000093400006001208009000900370016000040304860020200040000","958724631632159784147683592893475216761238459425916378516392847374861925289547163");
-            test_helper_with_answer!(solver, did_solve, "002700500804020970960800420500078040048000057006430200009000000210007004000040795","132794586854326971967851423521678349348219657796435218479583162215967834683142795");
-            test_helper_with_answer!(solver, did_solve, "102700580006005790709106042200060400560008210400000000070200000604050003000647008","132794586846325791759186342287961435563478219491532867978213654624859173315647928");
-            test_helper_with_answer!(solver, did_solve, "009700060100068009000294507000809600800000004030000982900005020605020093007940056","459731268172568349368294517724819635896352174531476982943685721685127493217943856");
-            test_helper_with_answer!(solver, did_solve, "000080930379500040000073500004300070810090000700406001107609854040700000926000003","265184937379562148481973526654318279813297465792456381137629854548731692926845713");
-            test_helper_with_answer!(solver, did_solve, "000500341105074600300100000409700100000090004600021970501000706800900425740005000","976582341125374698384169257439756182217893564658421973591248736863917425742635819");
-            test_helper_with_answer!(solver, did_solve, "307019400800452000000360000040700985730080200080240730620070004000094000090000170","367819452819452367452367819246731985731985246985246731623178594178594623594623178");
-            test_helper_with_answer!(solver, did_solve, "000009400500207001900534760800000674000490000154000020016050000289601000405002100","367819452548267391921534768893125674672493815154786923716358249289641537435972186");
-            test_helper_with_answer!(solver, did_solve, "006975213507631849093080675052006000000520086004890150240709000065100008001060004","486975213527631849193284675852416937719523486634897152248759361365142798971368524");
-            test_helper_with_answer!(solver, did_solve, "003095027900087430700200001108000005009802010352014080830970002507428190090506748","413695827925187436786243951148369275679852314352714689834971562567428193291536748");
-            test_helper_with_answer!(solver, did_solve, "652070381000680040038100690003800704829040016574260908000008170085019403007420000","652974381791683245438152697163895724829347516574261938946538172285719463317426859");
-            test_helper_with_answer!(solver, did_solve, "052304000437180920600500400086203107174065302300700008003018250060900014801052030","952374681437186925618529473586293147174865392329741568793418256265937814841652739");
-            test_helper_with_answer!(solver, did_solve, "241396080000085624805720100004009500176008900090030008020900370610072859059063200","241396785937185624865724193384219567176548932592637418428951376613472859759863241");
-            test_helper_with_answer!(solver, did_solve, "000074008401005320673102540060040030198023006034569080000007800047018265029450100","952374618481695327673182549765841932198723456234569781516237894347918265829456173");
-            test_helper_with_answer!(solver, did_solve, "135826000700000520000070001000000034073000215081243076006085740042967153057402609","135826497764139528298574361629751834473698215581243976916385742842967153357412689");
-            test_helper_with_answer!(solver, did_solve, "570362100064070030931485620020600094603759200095210000306007902009500008782006000","578362149264971835931485627127638594643759281895214376356847912419523768782196453");
-            test_helper_with_answer!(solver, did_solve, "060314057470650200305028694003000460740580000891402305007040500604801002000900746","269314857478659213315728694523197468746583129891462375937246581654871932182935746");
-            test_helper_with_answer!(solver, did_solve, "752630914149257683836941275983412567014570398075389421367894152498125736521763849","752638914149257683836941275983412567214576398675389421367894152498125736521763849");
-            test_helper_with_answer!(solver, did_solve, "248769510967513842315842769659380204183274956472956381794635128536128097821497635","248769513967513842315842769659381274183274956472956381794635128536128497821497635");
-            test_helper_with_answer!(solver, did_solve, "689124753357608241042075986401539628826417395593862174734956812965281437218743569","689124753357698241142375986471539628826417395593862174734956812965281437218743569");
-            test_helper_with_answer!(solver, did_solve, "841325967796418325532967418674831502059746831103592746927684153315279604468153279","841325967796418325532967418674831592259746831183592746927684153315279684468153279");
-            test_helper_with_answer!(solver, did_solve, "801230479497185326362749815613492587508361942924857631239574160186923754745618293","851236479497185326362749815613492587578361942924857631239574168186923754745618293");
-            test_helper_with_answer!(solver, did_solve, "519823046764519832803764591351482967482976315976350428197035684235648179648197253","519823746764519832823764591351482967482976315976351428197235684235648179648197253");
-            test_helper_with_answer!(solver, did_solve, "186275934725934816394816075067523491901687523253491687418762359672359048539148762","186275934725934816394816275867523491941687523253491687418762359672359148539148762");
-            test_helper_with_answer!(solver, did_solve, "275391640406752913139864527528179364643285791917436852792613485061548279850927136","275391648486752913139864527528179364643285791917436852792613485361548279854927136");
-            test_helper_with_answer!(solver, did_solve, "169284735208375916350196824730951682826743591915862473681427359593618247472039168","169284735248375916357196824734951682826743591915862473681427359593618247472539168");
-            test_helper_with_answer!(solver, did_solve, "001000000005000000900000200000040000000057000000310402040500630600400805009000000","261794358385621947974835216138246579426957183597318462742589631613472895859163724");
-            test_helper_with_answer!(solver, did_solve, "009000132060000000000800005100000008000100073000600000201000000500010000900060400","879456132465321789312897645154732968698145273723689514241973856586214397937568421");
-
-            test_helper!(solver, did_solve, "519872436643159827782463195495217368127386954836945271354791682268534719971028543");
-            test_helper!(solver, did_solve, "015369874748215936369748521497182365653497218182653749934871652526934187871526493");
-            test_helper!(solver, did_solve, "675491382238675914091238756984352167716984523352716849563147298147829635829563471");
-            test_helper!(solver, did_solve, "206473819819625734734981256567834192192756348348219567925367481481592673673148925");
-            test_helper!(solver, did_solve, "186293547540186293293547186435871962871962435962435871758619324324758619619324758");
-            test_helper!(solver, did_solve, "613574928475298136892316754549182673281763495367945812734859261120437589958621347");
-            test_helper!(solver, did_solve, "459821367281367549637549821765498213123075498948213675892136754316754982574982136");
-            test_helper!(solver, did_solve, "914562078256873941387149265549628137173495826862731594695287413728314659431956782");
-            test_helper!(solver, did_solve, "186293745547681293392745681658912437219437856734856912961324578403578169875169324");
-            test_helper!(solver, did_solve, "070030000000012000002005019050020073700000105140000906200054008630109502005267300");
-            test_helper!(solver, did_solve, "924000305010900280080007916200090700000704021030100590602010007500870100000006000");
-            test_helper!(solver, did_solve, "459063001000040000000095030000802640940000710607910520001600070074080000800030102");
-            test_helper!(solver, did_solve, "000070840080004027040002610090300750530006480000040396900081004060500900872000000");
-            test_helper!(solver, did_solve, "000489700000000809090070020000097000100804902028100003536008010082001305009003608");
-            test_helper!(solver, did_solve, "450063280300200000821590000703082050000037020000045000180009062000320010002050007");
-            test_helper!(solver, did_solve, "400703080000000406680400009005300000093820005064005300070530600031679050500280000");
-            test_helper!(solver, did_solve, "004680000300205419050930000230508001040190850000760090090020537000000006600050020");
-            test_helper!(solver, did_solve, "040000000000020740230400608020057900980000100001068000000502067800714092500600314");
-            test_helper!(solver, did_solve, "608040050300506097905000300030095208590700640004063010007001004800604500000000060");
-            test_helper!(solver, did_solve, "107008000693000020048000000980301460360052781500806230030500008700004006010000000");
-            test_helper!(solver, did_solve, "675321984312498576489657213967513042531284769248976135753142698896735421124869357");
-            test_helper!(solver, did_solve, "851276394726493185943581672217649853469358721538127946102764539674935218395812467");
-            test_helper!(solver, did_solve, "519728436728634915634519827347196582196285743285347691963851274851472369472963108");
-            test_helper!(solver, did_solve, "849673512637015498251894376476321985598467123312589764185946237964732851723158649");
-            test_helper!(solver, did_solve, "374289561298561347516347289985136724742958136163724958637492815851673492429815670");
-            test_helper!(solver, did_solve, "613429875924758631857316942279835164538641297146972580782563419491287356365194728");
-            test_helper!(solver, did_solve, "186352794235947186094861235342679851851423967967518342619285473473196528528734619");
-            test_helper!(solver, did_solve, "914738526783625049652941837495317682268459713371286954139872465546193278827564391");
-            test_helper!(solver, did_solve, "140627359359148627627359148296583471471296583583471296815764932764932815932815764");
-            test_helper!(solver, did_solve, "694513287531782946728649315172896534869435721453127698945371862317208459286954173");
-            test_helper!(solver, did_solve, "369241078758396124014785639876913245931452786425867391683129457192574863547638912");
-            test_helper!(solver, did_solve, "274638951638159472159274836782396145396541087541782693867913524425807319913425768");
-            test_helper!(solver, did_solve, "357194628419826375682573491061387954738459216945012783593241867124768539876935142");
-            test_helper!(solver, did_solve, "624579183795183462831462579573218946182946357469357218358621794216794835907830621");
-            test_helper!(solver, did_solve, "758421396963087214142639875314968527275143689896752431427316958631895742589274160");
-            test_helper!(solver, did_solve, "624579381597381642318642579839416725052893416461725893143067958276958134985134267");
-            test_helper!(solver, did_solve, "471368925925047683683592471816923754239475816754681239148236597597814362362759048");
-            test_helper!(solver, did_solve, "436987152978251364215463789182345697769128543354796821891532476647819235523074908");
-            test_helper!(solver, did_solve, "231407598859123746674985312346879025798251463512634987925316874487592631163748259");
-            test_helper!(solver, did_solve, "593280761284617359617593428468751932329468175751329846942876513876135294135942607");
-            test_helper!(solver, did_solve, "624831597801795426795624138257346819918257643340918752472163985163589274589472361");
-            test_helper!(solver, did_solve, "152038746398647215467512839571293468923864157084751392219386574836475921745129683");
-            test_helper!(solver, did_solve, "697825413341976825582413076925138764813764259476259138164597382238601597759382641");
-            test_helper!(solver, did_solve, "218435796345796128976128430459672813183549672762813549597261384834907261621384957");
-            test_helper!(solver, did_solve, "539824176761359428284671953690432781342187569817965034478516392156293847923748615");
-            test_helper!(solver, did_solve, "539824176428671539176935428853246917917350642642719853285467391760193285391582764");
-            test_helper!(solver, did_solve, "524719638368542971791086254870423519159867342432195786617238495945671823283954167");
-            test_helper!(solver, did_solve, "957812634281306957634579281468937125793251468025684793519428376842763519376195842");
-            test_helper!(solver, did_solve, "134258697967134528258967314813725906496013275725496183349581762672349851581672439");
-            
-            42
-        });
-        assert_eq!(42, x);
-    }
-
-    #[test]
-    fn test_files() {
-        init();
-        let did_solve = AtomicBool::new(false);
-        with_multithreaded_solver(|solver| {
-            let mut total_time_spent_solving = Duration::new(0, 0);
-            let mut total_sudokus = 0;
-            let paths = fs::read_dir("../test_files").unwrap();
-            let mut paths = paths.flatten().collect::<std::vec::Vec<_>>();
-            paths.sort_by_key(|x| (x.path().as_os_str().len(), x.path()));
-            for path in paths {
-                let path = path.path();
-                let path_str: String = path.display().to_string();
-                print!("Solving file {}...", path_str);
-                let test_start = std::time::Instant::now();
-                let file = fs::File::open(path).unwrap();
-                let mut reader = BufReader::new(file);
-                let mut buf = String::with_capacity(81 * 2 + 1);
-
-                //Discard first line with headers: PUZZLES,SOLUTIONS,char_count
-                _ = reader.read_line(&mut buf);
-
-                loop {
-                    buf.clear();
-                    match reader.read_line(&mut buf) {
-                        Ok(0) => break,
-                        Err(_) => break,
-                        //This checks that the solver is able to arrive at the solution specified in the CSV file
-                        // _ => test_helper_with_answer!(solver, did_solve, &buf[0..81], &buf[82..163]),
-                        //This checks that the solver is able to arrive at _some_ (any) solution
-                        _ => {
-                            total_sudokus += 1;
-                            timed_test_helper!(solver, did_solve, &buf[0..81], total_time_spent_solving);
-                        },
-                    }
-                }
-                println!(
-                    "\rFinished solving file {} in {:?}",
-                    path_str,
-                    test_start.elapsed()
-                );
-            }
-            println!("Solved {} sudokus in {:?} ({:?} / sudoku)", total_sudokus, total_time_spent_solving, total_time_spent_solving/total_sudokus);
-        });
-    }
+            })
+            .unwrap();
+    });
 }
@@ -0,0 +1,435 @@
+// Parallel search that splits the search tree between threads, instead of racing whole searches in
+// different cell orders like `with_multithreaded_solver`. Every worker runs fewest-candidates-first
+// backtracking on a subproblem. Each idle worker is claimed by one of the busy ones, which hands off
+// the untried digits of its shallowest stack frame as new subproblems.
+// Progress is estimated like in `solve_single_thread_with_progress`, with every subproblem
+// carrying its share of the whole tree.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use crate::{
+    CandidateSet, CandidateSetIterator, DEFAULT_CHECK_INTERVAL, SolutionAction, Solutions,
+    SolveLimits, SolveOutcome, SolveProgress, SolveStats, Sudoku, fewest_candidates_first,
+};
+
+struct WorkQueue {
+    // Partially filled grids nobody has started on yet, with their share of the whole tree
+    tasks: Mutex<Vec<(Sudoku, f64)>>,
+    task_added: Condvar,
+    // Workers waiting for a task, only written with the lock held
+    idle: AtomicUsize,
+    // Waiting workers no busy worker has claimed to hand a task to yet
+    hungry: AtomicUsize,
+    done: AtomicBool,
+    threads: usize,
+    progress: Mutex<Progress>,
+    limits: SolveLimits,
+    // Set when the limits ran out or the token of the limits was cancelled before the search was done
+    limit_reached: AtomicBool,
+    cancelled: AtomicBool,
+    // Solutions handed to the callback or counted
+    solutions: AtomicU64,
+}
+
+// Progress of all workers together
+#[derive(Default)]
+struct Progress {
+    nodes: u64,
+    // Share of the tree the finished subproblems covered
+    searched: f64,
+    // Largest estimate reported so far
+    reported: f64,
+}
+
+impl WorkQueue {
+    // Blocks until there is a task, None once the search is over
+    fn next_task(&self) -> Option<(Sudoku, f64)> {
+        let mut tasks = self.tasks.lock().unwrap();
+        loop {
+            if self.done.load(Ordering::Acquire) {
+                return None;
+            }
+            if let Some(task) = tasks.pop() {
+                return Some(task);
+            }
+            if self.idle.load(Ordering::Relaxed) + 1 == self.threads {
+                //Everybody else is waiting as well, the whole tree has been searched
+                self.done.store(true, Ordering::Release);
+                self.task_added.notify_all();
+                return None;
+            }
+            self.idle.fetch_add(1, Ordering::Relaxed);
+            self.hungry.fetch_add(1, Ordering::Relaxed);
+            tasks = self.task_added.wait(tasks).unwrap();
+            self.idle.fetch_sub(1, Ordering::Relaxed);
+            //Every waiting worker wakes up on a share and asks again if it is still without a task,
+            //so taking back one request here keeps the count right once they all did
+            self.take_request();
+        }
+    }
+
+    // Takes one waiting worker's request for a task off the count, false if there was none
+    fn take_request(&self) -> bool {
+        self.hungry
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |hungry| {
+                hungry.checked_sub(1)
+            })
+            .is_ok()
+    }
+
+    fn share(&self, new_tasks: Vec<(Sudoku, f64)>) {
+        self.tasks.lock().unwrap().extend(new_tasks);
+        self.task_added.notify_all();
+    }
+
+    fn add_searched(&self, share: f64) {
+        self.progress.lock().unwrap().searched += share;
+    }
+
+    // Reports the subproblems finished so far plus `partial`, the part of its own subproblem the
+    // calling worker searched. The other workers' partial progress is left out, so the estimate
+    // lags behind a little. Returns the search nodes of all workers.
+    fn report(&self, depth: usize, partial: f64, progress: &impl Fn(&SolveProgress)) -> u64 {
+        //Held while reporting, so reports come one at a time and in order
+        let mut total = self.progress.lock().unwrap();
+        total.nodes += DEFAULT_CHECK_INTERVAL as u64;
+        total.reported = total.reported.max((total.searched + partial).min(1.0));
+        progress(&SolveProgress {
+            nodes: total.nodes,
+            depth,
+            estimated_fraction: total.reported,
+        });
+        total.nodes
+    }
+
+    // Ends the search for every worker once the limits ran out or their token was cancelled
+    fn check_limits(&self, nodes: u64) {
+        if self.limits.is_exceeded(nodes) {
+            self.limit_reached.store(true, Ordering::Release);
+            self.finish();
+        } else if self.limits.is_cancelled() {
+            self.cancelled.store(true, Ordering::Release);
+            self.finish();
+        }
+    }
+
+    fn finish(&self) {
+        let _tasks = self.tasks.lock().unwrap();
+        self.done.store(true, Ordering::Release);
+        self.task_added.notify_all();
+    }
+}
+
+// Same search as `solve_single_thread_dynamic`, returns once `on_solution` returns true, the
+// subproblem is exhausted or another worker finished the search. `share` is the share of the
+// whole tree below `sudoku`.
+fn search(
+    sudoku: &mut Sudoku,
+    share: f64,
+    allowed_candidates: &[CandidateSet; 81],
+    queue: &WorkQueue,
+    on_solution: &impl Fn(&Sudoku) -> bool,
+    progress: &impl Fn(&SolveProgress),
+) {
+    // cells[..depth] are the cells of the current stack frames, cells[depth..cell_count] are still empty
+    let mut cells: [u8; 81] = [0; _];
+    let mut cell_count = 0;
+    for index in 0..81 {
+        if sudoku.is_missing(index) {
+            cells[cell_count] = index;
+            cell_count += 1;
+        }
+    }
+    let mut stack: [CandidateSetIterator; 81] = [CandidateSetIterator::empty(); _];
+    //Number of candidates each stack frame started out with, and how many of them went to other
+    //workers. Those report their share themselves once done.
+    let mut widths: [u8; 81] = [0; _];
+    let mut donated: [u8; 81] = [0; _];
+    let mut donated_share = 0.0;
+    let mut depth = 0;
+    let mut counter = 0;
+
+    loop {
+        if depth == cell_count {
+            if on_solution(sudoku) {
+                return;
+            }
+        } else {
+            let candidates =
+                fewest_candidates_first(sudoku, allowed_candidates, &mut cells[depth..cell_count]);
+            widths[depth] = candidates.len() as u8;
+            donated[depth] = 0;
+            stack[depth] = candidates.into_iter();
+            depth += 1;
+        }
+
+        loop {
+            if depth == 0 {
+                //Subproblem is exhausted
+                queue.add_searched(share - donated_share);
+                return;
+            }
+            if let Some(digit) = stack[depth - 1].next() {
+                sudoku.set(cells[depth - 1], digit);
+                break;
+            }
+            sudoku.set(cells[depth - 1], 0);
+            depth -= 1;
+        }
+
+        //Both are plain atomic loads, cheap next to the candidate scan above
+        if queue.done.load(Ordering::Acquire) {
+            return;
+        }
+        counter += 1;
+        if counter >= DEFAULT_CHECK_INTERVAL {
+            counter = 0;
+            let frames = (0..depth).map(|k| {
+                let finished = widths[k] as u32 - stack[k].len() - 1 - donated[k] as u32;
+                (finished, widths[k] as u32)
+            });
+            let own = SolveProgress::estimate(0, frames);
+            let nodes = queue.report(own.depth, share * own.estimated_fraction, progress);
+            queue.check_limits(nodes);
+        }
+        //Claim a waiting worker so the other busy ones do not hand it tasks as well
+        if queue.hungry.load(Ordering::Relaxed) > 0 && queue.take_request() {
+            //The shallowest open frame holds the largest untried subtrees
+            if let Some(frame) = (0..depth).find(|k| !stack[*k].is_empty()) {
+                let subtree = widths[..=frame]
+                    .iter()
+                    .fold(share, |subtree, width| subtree / *width as f64);
+                let mut new_tasks = Vec::new();
+                for digit in &mut stack[frame] {
+                    let mut task = sudoku.clone();
+                    for cell in &cells[frame + 1..depth] {
+                        task.set(*cell, 0);
+                    }
+                    task.set(cells[frame], digit);
+                    new_tasks.push((task, subtree));
+                }
+                donated[frame] += new_tasks.len() as u8;
+                donated_share += new_tasks.len() as f64 * subtree;
+                queue.share(new_tasks);
+            } else {
+                //Nothing left to hand off, leave the worker to somebody else
+                queue.hungry.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+fn run_workers(
+    sudoku: &Sudoku,
+    allowed_candidates: &[CandidateSet; 81],
+    threads: usize,
+    limits: &SolveLimits,
+    on_solution: impl Fn(&Sudoku, &WorkQueue) -> bool + Sync,
+    progress: impl Fn(&SolveProgress) + Sync,
+) -> SolveOutcome {
+    let start = Instant::now();
+    let queue = WorkQueue {
+        tasks: Mutex::new(vec![(sudoku.clone(), 1.0)]),
+        task_added: Condvar::new(),
+        idle: AtomicUsize::new(0),
+        hungry: AtomicUsize::new(0),
+        done: AtomicBool::new(false),
+        threads: threads.max(1),
+        progress: Mutex::new(Progress::default()),
+        limits: limits.clone(),
+        limit_reached: AtomicBool::new(false),
+        cancelled: AtomicBool::new(false),
+        solutions: AtomicU64::new(0),
+    };
+    //An easy puzzle may be done before the first check
+    queue.check_limits(0);
+    thread::scope(|scope| {
+        for _ in 0..queue.threads {
+            scope.spawn(|| {
+                while let Some((mut task, share)) = queue.next_task() {
+                    search(
+                        &mut task,
+                        share,
+                        allowed_candidates,
+                        &queue,
+                        &|solution| on_solution(solution, &queue),
+                        &progress,
+                    );
+                }
+            });
+        }
+    });
+    let stats = SolveStats {
+        nodes: queue.progress.lock().unwrap().nodes,
+        solutions: queue.solutions.load(Ordering::Relaxed),
+        elapsed: start.elapsed(),
+    };
+    if queue.limit_reached.load(Ordering::Acquire) {
+        SolveOutcome::LimitReached(stats)
+    } else if queue.cancelled.load(Ordering::Acquire) {
+        SolveOutcome::Cancelled(stats)
+    } else {
+        SolveOutcome::Finished(stats)
+    }
+}
+
+/// Searches with `threads` workers that split the search tree between them. `callback` is handed
+/// every solution until it accepts one or aborts, one call at a time like with `Solver::solve`.
+/// The workers never search the same subtree, so no solution is handed to it twice. `sudoku` is
+/// left as it was handed in.
+pub fn solve_parallel<R: Into<SolutionAction>>(
+    sudoku: &mut Sudoku,
+    allowed_candidates: &[CandidateSet; 81],
+    callback: impl FnMut(&Sudoku) -> R + Send,
+    threads: usize,
+) -> Solutions {
+    solve_parallel_with_progress(sudoku, allowed_candidates, callback, threads, |_| {})
+}
+
+/// Like `solve_parallel`, and also reports its progress to `progress` every
+/// `DEFAULT_CHECK_INTERVAL` search nodes of any worker, one call at a time. The node count and
+/// estimate cover all workers, the depth is that of the reporting one.
+pub fn solve_parallel_with_progress<R: Into<SolutionAction>>(
+    sudoku: &mut Sudoku,
+    allowed_candidates: &[CandidateSet; 81],
+    callback: impl FnMut(&Sudoku) -> R + Send,
+    threads: usize,
+    progress: impl Fn(&SolveProgress) + Sync,
+) -> Solutions {
+    let (_, solutions) = solve_parallel_with_limits(
+        sudoku,
+        allowed_candidates,
+        callback,
+        threads,
+        &SolveLimits::default(),
+        progress,
+    );
+    solutions
+}
+
+/// Like `solve_parallel_with_progress`, but gives up once `limits` run out. The node limit covers
+/// all workers, and cancelling the token of `limits` stops all of them.
+pub fn solve_parallel_with_limits<R: Into<SolutionAction>>(
+    sudoku: &mut Sudoku,
+    allowed_candidates: &[CandidateSet; 81],
+    callback: impl FnMut(&Sudoku) -> R + Send,
+    threads: usize,
+    limits: &SolveLimits,
+    progress: impl Fn(&SolveProgress) + Sync,
+) -> (SolveOutcome, Solutions) {
+    let shared = Mutex::new((callback, Solutions::default()));
+    let outcome = run_workers(
+        sudoku,
+        allowed_candidates,
+        threads,
+        limits,
+        |solution, queue| {
+            let (callback, found) = &mut *shared.lock().unwrap();
+            //A solution that lost the race to the lock is dropped
+            if queue.done.load(Ordering::Acquire) {
+                return true;
+            }
+            queue.solutions.fetch_add(1, Ordering::Relaxed);
+            if found.keep(solution, callback(solution).into()) {
+                queue.finish();
+                return true;
+            }
+            false
+        },
+        progress,
+    );
+    (outcome, shared.into_inner().unwrap().1)
+}
+
+/// Counts solutions with `threads` workers, stopping early once `limit` are found
+pub fn count_solutions_parallel(sudoku: &Sudoku, limit: usize, threads: usize) -> usize {
+    let outcome =
+        count_solutions_parallel_with_limits(sudoku, limit, threads, &SolveLimits::default());
+    outcome.stats().solutions as usize
+}
+
+/// Like `count_solutions_parallel`, but gives up once `limits` run out. The solutions of the stats
+/// are the count, which only covers part of the tree unless the outcome is `Finished`.
+pub fn count_solutions_parallel_with_limits(
+    sudoku: &Sudoku,
+    limit: usize,
+    threads: usize,
+    limits: &SolveLimits,
+) -> SolveOutcome {
+    let mut outcome = run_workers(
+        sudoku,
+        &crate::ALL_CANDIDATES,
+        threads,
+        limits,
+        |_, queue| {
+            if queue.solutions.fetch_add(1, Ordering::Relaxed) + 1 >= limit as u64 {
+                queue.finish();
+                return true;
+            }
+            false
+        },
+        |_| {},
+    );
+    //Workers racing for the last solutions may count past the limit
+    match &mut outcome {
+        SolveOutcome::Finished(stats)
+        | SolveOutcome::LimitReached(stats)
+        | SolveOutcome::Cancelled(stats) => stats.solutions = stats.solutions.min(limit as u64),
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ALL_CANDIDATES, DynamicBacktracking, MaybeValid, SolverBackend};
+
+    #[test]
+    fn test_solves_and_counts() {
+        let sudoku: Sudoku =
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003"
+                .into();
+        let solution: Sudoku =
+            "265184937379562148481973526654318279813297465792456381137629854548731692926845713"
+                .into();
+        for threads in [1, 4] {
+            let did_solve = AtomicBool::new(false);
+            let mut puzzle = sudoku.clone();
+            solve_parallel(
+                &mut puzzle,
+                &ALL_CANDIDATES,
+                |s| {
+                    assert!(s.is_valid());
+                    did_solve.store(*s == solution, Ordering::Release);
+                    did_solve.load(Ordering::Acquire)
+                },
+                threads,
+            );
+            assert!(did_solve.load(Ordering::Acquire));
+            assert!(puzzle == sudoku);
+            assert_eq!(count_solutions_parallel(&sudoku, usize::MAX, threads), 1);
+        }
+
+        let ambiguous: Sudoku =
+            "000720030000000000106008709003091000580407200000000006840650010600143900005000402"
+                .into();
+        let count = DynamicBacktracking.count_solutions(&ambiguous, usize::MAX);
+        assert!(count > 1);
+        for threads in [1, 3, 8] {
+            assert_eq!(
+                count_solutions_parallel(&ambiguous, usize::MAX, threads),
+                count
+            );
+            assert_eq!(count_solutions_parallel(&ambiguous, 2, threads), 2);
+        }
+
+        //No digit left for the last cell of the first row
+        let unsolvable: Sudoku =
+            "123456780000000009000000000000000000000000000000000000000000000000000000000000000"
+                .into();
+        assert_eq!(count_solutions_parallel(&unsolvable, usize::MAX, 4), 0);
+    }
+}
@@ -0,0 +1,429 @@
+// Pencil-mark grid: a candidate mask per cell, so candidates can be eliminated from one cell alone.
+// `Sudoku` only tracks candidates per row, column and subgrid.
+
+use crate::{CandidateSet, Sudoku};
+
+const fn build_units() -> [[u8; 9]; 27] {
+    let mut units = [[0; 9]; 27];
+    let mut i = 0;
+    while i < 9 {
+        let mut j = 0;
+        while j < 9 {
+            units[i][j] = (i * 9 + j) as u8;
+            units[9 + i][j] = (j * 9 + i) as u8;
+            units[18 + i][j] = ((i / 3) * 27 + (i % 3) * 3 + (j / 3) * 9 + j % 3) as u8;
+            j += 1;
+        }
+        i += 1;
+    }
+    units
+}
+
+// Rows are units 0..9, columns 9..18 and subgrids 18..27
+pub(crate) const UNITS: [[u8; 9]; 27] = build_units();
+
+pub(crate) fn units_of(index: u8) -> [usize; 3] {
+    [
+        Sudoku::row_index(index) as usize,
+        9 + Sudoku::col_index(index) as usize,
+        18 + Sudoku::grid_index(index) as usize,
+    ]
+}
+
+/// Filled cells have an empty candidate mask
+#[derive(Clone, PartialEq, Eq)]
+pub struct PencilGrid {
+    sudoku: Sudoku,
+    pub(crate) candidates: [CandidateSet; 81],
+}
+
+impl PencilGrid {
+    pub fn get(&self, index: u8) -> u8 {
+        self.sudoku.get(index)
+    }
+
+    pub fn is_missing(&self, index: u8) -> bool {
+        self.sudoku.is_missing(index)
+    }
+
+    pub fn candidates(&self, index: u8) -> CandidateSet {
+        self.candidates[index as usize]
+    }
+
+    /// Usable as the allowed candidates of `solve_single_thread` and `Solver::solve_with_candidates`
+    pub fn candidate_masks(&self) -> &[CandidateSet; 81] {
+        &self.candidates
+    }
+
+    pub fn sudoku(&self) -> &Sudoku {
+        &self.sudoku
+    }
+
+    pub fn is_solved(&self) -> bool {
+        (0..81).all(|index| !self.is_missing(index))
+    }
+
+    /// Removes `digit` from the candidates of one cell, returns whether it was a candidate
+    pub fn eliminate(&mut self, index: u8, digit: u8) -> bool {
+        let was_candidate = self.candidates[index as usize].contains(digit);
+        self.candidates[index as usize] &= !(1 << (digit - 1));
+        was_candidate
+    }
+
+    /// Fills the cell and removes `digit` from the candidates of every peer
+    pub fn place(&mut self, index: u8, digit: u8) {
+        self.sudoku.set(index, digit);
+        self.candidates[index as usize] = CandidateSet::empty();
+        for unit in units_of(index) {
+            for peer in UNITS[unit] {
+                self.candidates[peer as usize] &= !(1 << (digit - 1));
+            }
+        }
+    }
+}
+
+impl From<&Sudoku> for PencilGrid {
+    fn from(sudoku: &Sudoku) -> Self {
+        PencilGrid {
+            sudoku: sudoku.clone(),
+            candidates: core::array::from_fn(|index| {
+                if sudoku.is_missing(index as u8) {
+                    sudoku.get_candidates(index as u8)
+                } else {
+                    CandidateSet::empty()
+                }
+            }),
+        }
+    }
+}
+
+impl From<PencilGrid> for Sudoku {
+    fn from(grid: PencilGrid) -> Self {
+        grid.sudoku
+    }
+}
+
+impl PencilGrid {
+    /// The 729 character format, 9 characters per cell with digit `d` at position `d - 1` when it
+    /// is a candidate (or the filled value) and `.` otherwise
+    pub fn to_candidate_string(&self) -> String {
+        let mut out = String::with_capacity(729);
+        for index in 0..81 {
+            for digit in 1..=9 {
+                if self.cell_digits(index).contains(digit) {
+                    out.push((b'0' + digit) as char);
+                } else {
+                    out.push('.');
+                }
+            }
+        }
+        out
+    }
+
+    fn cell_digits(&self, index: u8) -> CandidateSet {
+        match self.get(index) {
+            0 => self.candidates(index),
+            val => CandidateSet(1 << (val - 1)),
+        }
+    }
+
+    /// Reads the 729 character format together with the filled cells, which it cannot tell apart
+    /// from naked singles on its own. Every filled cell of `givens` must have its digit as the only
+    /// digit in `candidates`.
+    pub fn from_candidate_string(
+        givens: &Sudoku,
+        candidates: &str,
+    ) -> Result<Self, ParsePencilGridError> {
+        let compact: Vec<u8> = candidates
+            .bytes()
+            .filter(|c| !c.is_ascii_whitespace())
+            .collect();
+        if let Some(c) = compact.iter().find(|c| !c.is_ascii_digit() && **c != b'.') {
+            return Err(ParsePencilGridError::InvalidCharacter(*c as char));
+        }
+        let cells = parse_candidate_string(&compact)
+            .ok_or(ParsePencilGridError::WrongCellCount(compact.len() / 9))?;
+        PencilGrid::from_givens(givens, cells)
+    }
+
+    /// Reads the candidate grid written by `Display` together with the filled cells, which it
+    /// writes as bare digits like naked singles. See `from_candidate_string`.
+    pub fn from_grid_string(givens: &Sudoku, grid: &str) -> Result<Self, ParsePencilGridError> {
+        PencilGrid::from_givens(givens, parse_grid_string(grid)?)
+    }
+
+    fn from_givens(
+        givens: &Sudoku,
+        mut cells: Vec<(CandidateSet, bool)>,
+    ) -> Result<Self, ParsePencilGridError> {
+        if cells.len() != 81 {
+            return Err(ParsePencilGridError::WrongCellCount(cells.len()));
+        }
+        for (index, (digits, filled)) in cells.iter_mut().enumerate() {
+            let digit = givens.get(index as u8);
+            if digit != 0 {
+                if *digits != CandidateSet(1 << (digit - 1)) {
+                    return Err(ParsePencilGridError::InvalidPlacement(index as u8));
+                }
+                *filled = true;
+            }
+        }
+        PencilGrid::from_cells(&cells)
+    }
+
+    // Cells are (digits, filled), a filled cell has to hold exactly one digit that none of the
+    // filled cells before it in its units hold
+    fn from_cells(cells: &[(CandidateSet, bool)]) -> Result<Self, ParsePencilGridError> {
+        if cells.len() != 81 {
+            return Err(ParsePencilGridError::WrongCellCount(cells.len()));
+        }
+        let mut grid = PencilGrid {
+            sudoku: [0_u8; 81].into(),
+            candidates: [CandidateSet::empty(); 81],
+        };
+        for (index, (digits, filled)) in cells.iter().enumerate() {
+            let index = index as u8;
+            if !filled {
+                grid.candidates[index as usize] = *digits;
+                continue;
+            }
+            match digits.into_iter().next() {
+                Some(digit)
+                    if digits.len() == 1 && grid.sudoku.get_candidates(index).contains(digit) =>
+                {
+                    grid.sudoku.set(index, digit)
+                }
+                _ => return Err(ParsePencilGridError::InvalidPlacement(index)),
+            }
+        }
+        Ok(grid)
+    }
+}
+
+// Every run of digits is one unsolved cell
+fn parse_grid_string(s: &str) -> Result<Vec<(CandidateSet, bool)>, ParsePencilGridError> {
+    let mut cells = Vec::with_capacity(81);
+    let mut current: Option<CandidateSet> = None;
+    for c in s.chars() {
+        match c {
+            '1'..='9' => *current.get_or_insert(CandidateSet::empty()) |= 1 << (c as u8 - b'1'),
+            _ if c.is_whitespace() || "|-+.:'".contains(c) => cells.extend(current.take()),
+            _ => return Err(ParsePencilGridError::InvalidCharacter(c)),
+        }
+    }
+    cells.extend(current);
+    Ok(cells.into_iter().map(|digits| (digits, false)).collect())
+}
+
+// Every run of 9 characters is one unsolved cell
+fn parse_candidate_string(compact: &[u8]) -> Option<Vec<(CandidateSet, bool)>> {
+    if compact.len() != 729 || !compact.iter().all(|c| c.is_ascii_digit() || *c == b'.') {
+        return None;
+    }
+    let cells = compact
+        .chunks(9)
+        .map(|chunk| {
+            let mut digits = CandidateSet::empty();
+            for c in chunk.iter().filter(|c| matches!(c, b'1'..=b'9')) {
+                digits |= 1 << (c - b'1');
+            }
+            (digits, false)
+        })
+        .collect();
+    Some(cells)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParsePencilGridError {
+    WrongCellCount(usize),
+    InvalidCharacter(char),
+    /// A filled cell with other than one digit, or with the digit of a filled peer
+    InvalidPlacement(u8),
+}
+
+impl core::fmt::Display for ParsePencilGridError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParsePencilGridError::WrongCellCount(count) => {
+                write!(f, "expected 81 cells, found {}", count)
+            }
+            ParsePencilGridError::InvalidCharacter(c) => write!(f, "unexpected character {:?}", c),
+            ParsePencilGridError::InvalidPlacement(index) => {
+                write!(f, "invalid digit in filled cell {}", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParsePencilGridError {}
+
+/// Accepts either the 729 character candidate string or the candidate grid written by `Display`.
+/// Neither tells filled cells apart from naked singles, so every cell is read as unsolved, see
+/// `from_candidate_string` and `from_grid_string`. In the grid every run of digits is one cell, box
+/// separators (`|`, `-`, `+`, `.`, `:`, `'`) and whitespace are skipped.
+impl core::str::FromStr for PencilGrid {
+    type Err = ParsePencilGridError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let compact: Vec<u8> = s.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+        match parse_candidate_string(&compact) {
+            Some(cells) => PencilGrid::from_cells(&cells),
+            None => PencilGrid::from_cells(&parse_grid_string(s)?),
+        }
+    }
+}
+
+/// Writes the candidate grid, one column per cell padded to the widest cell, with box separators.
+/// Filled cells are written as their digit. A cell without candidates, which only a contradiction
+/// leaves, has no notation in the grid and is written as `.`, only the candidate string keeps it.
+impl core::fmt::Display for PencilGrid {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let cells: [String; 81] =
+            core::array::from_fn(|index| match self.cell_digits(index as u8) {
+                digits if digits.is_empty() => ".".to_string(),
+                digits => digits
+                    .into_iter()
+                    .map(|digit| (b'0' + digit) as char)
+                    .collect(),
+            });
+        let width = cells.iter().map(String::len).max().unwrap();
+        let separator = format!("+{}", "-".repeat(3 * (width + 1) + 1)).repeat(3) + "+";
+        for row in 0..9 {
+            if row % 3 == 0 {
+                writeln!(f, "{}", separator)?;
+            }
+            for col in 0..9 {
+                if col % 3 == 0 {
+                    "| ".fmt(f)?;
+                }
+                write!(f, "{:<width$} ", cells[row * 9 + col])?;
+            }
+            writeln!(f, "|")?;
+        }
+        write!(f, "{}", separator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{Technique, solve_with_techniques};
+
+    #[test]
+    fn test_place_and_eliminate() {
+        let sudoku: Sudoku =
+            "000720030007006820106008709003091000580407200000000006840650010600143900005000402"
+                .into();
+        let mut grid = PencilGrid::from(&sudoku);
+        assert!(grid.candidates(0) == sudoku.get_candidates(0));
+        assert!(grid.candidates(1).is_empty() == !sudoku.is_missing(1));
+
+        assert!(grid.candidates(0).contains(9));
+        assert!(grid.eliminate(0, 9));
+        assert!(!grid.eliminate(0, 9));
+        assert!(!grid.candidates(0).contains(9));
+
+        let digit = grid.candidates(2).into_iter().next().unwrap();
+        grid.place(2, digit);
+        assert_eq!(grid.get(2), digit);
+        assert!(grid.candidates(2).is_empty());
+        for peer in [0, 8, 11, 20, 65] {
+            assert!(!grid.candidates(peer).contains(digit));
+        }
+
+        let back: Sudoku = grid.into();
+        assert_eq!(back.get(2), digit);
+        assert!(!back.get_candidates(0).contains(digit));
+    }
+
+    #[test]
+    fn test_text_formats_round_trip() {
+        let sudoku: Sudoku =
+            "001000000005000000900000200000040000000057000000310402040500630600400805009000000"
+                .into();
+        let mut grid = solve_with_techniques(&sudoku, Technique::XWing).grid;
+        assert!(!grid.is_solved());
+        // An elimination `Sudoku` alone could not represent
+        let index = (0..81)
+            .find(|index| grid.candidates(*index).len() > 2)
+            .unwrap();
+        let digit = grid.candidates(index).into_iter().next().unwrap();
+        grid.eliminate(index, digit);
+
+        assert_round_trip(&grid);
+
+        //Naked singles and cells without candidates are not filled cells
+        let unsolved: Vec<u8> = (0..81).filter(|index| grid.is_missing(*index)).collect();
+        let (single, empty) = (unsolved[0], unsolved[1]);
+        let digits: Vec<u8> = grid.candidates(single).into_iter().collect();
+        for digit in &digits[1..] {
+            grid.eliminate(single, *digit);
+        }
+        for digit in grid.candidates(empty) {
+            grid.eliminate(empty, digit);
+        }
+        let candidate_string = grid.to_candidate_string();
+        let parsed = PencilGrid::from_candidate_string(grid.sudoku(), &candidate_string).unwrap();
+        assert!(parsed == grid);
+        assert!(parsed.is_missing(single) && parsed.candidates(single).len() == 1);
+        assert!(parsed.is_missing(empty) && parsed.candidates(empty).is_empty());
+        //The grid has no notation for a cell without candidates
+        assert!(PencilGrid::from_grid_string(grid.sudoku(), &grid.to_string()).is_err());
+        grid.candidates[empty as usize] = CandidateSet(0b11);
+        assert_round_trip(&grid);
+
+        //Without the filled cells every cell is unsolved
+        let unsolved: PencilGrid = grid.to_candidate_string().parse().unwrap();
+        assert!((0..81).all(|index| unsolved.is_missing(index)));
+        let unsolved: PencilGrid = grid.to_string().parse().unwrap();
+        assert!((0..81).all(|index| unsolved.is_missing(index)));
+    }
+
+    fn assert_round_trip(grid: &PencilGrid) {
+        let candidate_string = grid.to_candidate_string();
+        assert_eq!(candidate_string.len(), 729);
+        assert!(
+            PencilGrid::from_candidate_string(grid.sudoku(), &candidate_string).unwrap() == *grid
+        );
+
+        //Filled cells are bare digits, as in the grids of other tools
+        let text = grid.to_string();
+        assert_eq!(text.lines().count(), 13);
+        assert!(
+            text.chars()
+                .all(|c| c.is_ascii_digit() || " |-+\n".contains(c))
+        );
+        assert!(PencilGrid::from_grid_string(grid.sudoku(), &text).unwrap() == *grid);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "| 12 3 |".parse::<PencilGrid>().err(),
+            Some(ParsePencilGridError::WrongCellCount(2))
+        );
+        assert_eq!(
+            "| 12 x |".parse::<PencilGrid>().err(),
+            Some(ParsePencilGridError::InvalidCharacter('x'))
+        );
+        assert_eq!(
+            "| 12 0 |".parse::<PencilGrid>().err(),
+            Some(ParsePencilGridError::InvalidCharacter('0'))
+        );
+        //Two filled cells of the first row with the same digit
+        let clashing = format!("1 1 {}", "123456789 ".repeat(79));
+        assert_eq!(
+            PencilGrid::from_grid_string(&Sudoku::from("11"), &clashing).err(),
+            Some(ParsePencilGridError::InvalidPlacement(1))
+        );
+        let givens = Sudoku::from("1");
+        assert_eq!(
+            PencilGrid::from_grid_string(&givens, &format!("12 {}", "1 ".repeat(80))).err(),
+            Some(ParsePencilGridError::InvalidPlacement(0))
+        );
+        assert_eq!(
+            PencilGrid::from_candidate_string(&givens, &".2.......".repeat(81)).err(),
+            Some(ParsePencilGridError::InvalidPlacement(0))
+        );
+    }
+}
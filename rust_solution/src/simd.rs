@@ -0,0 +1,199 @@
+// Bulk solving with one puzzle per SIMD lane. Naked and hidden singles are propagated for all lanes
+// at once, which finishes most easy and medium puzzles on its own. Lanes that still have open cells
+// afterwards fall back to the scalar search, seeded with the candidates propagation left them.
+
+use std::simd::cmp::SimdPartialEq;
+use std::simd::{Mask, Select, Simd};
+
+use crate::pencil::UNITS;
+use crate::{CandidateSet, Sudoku, solve_single_thread_dynamic};
+
+pub const LANES: usize = 16;
+
+type Lanes = Simd<u16, LANES>;
+type LaneMask = Mask<i16, LANES>;
+
+const ALL_DIGITS: Lanes = Simd::from_array([0b111111111; LANES]);
+const NONE: Lanes = Simd::from_array([0; LANES]);
+const ONE: Lanes = Simd::from_array([1; LANES]);
+
+// Candidate masks of every cell, lane `k` holds puzzle `k`. Filled cells are single digit masks.
+struct LaneGrid {
+    cells: [Lanes; 81],
+}
+
+fn is_single(m: Lanes) -> LaneMask {
+    (m & (m - ONE)).simd_eq(NONE) & m.simd_ne(NONE)
+}
+
+impl LaneGrid {
+    // Lanes past the end of `puzzles` repeat the last puzzle
+    fn load(puzzles: &[Sudoku]) -> Self {
+        LaneGrid {
+            cells: core::array::from_fn(|index| {
+                Lanes::from_array(core::array::from_fn(|lane| {
+                    match puzzles[lane.min(puzzles.len() - 1)].get(index as u8) {
+                        0 => 0b111111111,
+                        val => 1 << (val - 1),
+                    }
+                }))
+            }),
+        }
+    }
+
+    // Runs naked and hidden singles until nothing changes in any lane, returns the lanes that hit a
+    // contradiction
+    fn propagate(&mut self) -> LaneMask {
+        let mut broken = LaneMask::splat(false);
+        loop {
+            let mut changed = LaneMask::splat(false);
+            for unit in UNITS {
+                //Naked singles: remove the digits of solved cells from the rest of the unit
+                let mut solved = NONE;
+                let mut duplicate = NONE;
+                for index in unit {
+                    let m = self.cells[index as usize];
+                    let single = is_single(m).select(m, NONE);
+                    duplicate |= solved & single;
+                    solved |= single;
+                }
+                broken |= duplicate.simd_ne(NONE);
+
+                //Hidden singles: a digit with one possible cell in the unit goes there
+                let mut once = NONE;
+                let mut twice = NONE;
+                for index in unit {
+                    let old = self.cells[index as usize];
+                    let m = is_single(old).select(old, old & !solved);
+                    changed |= m.simd_ne(old);
+                    twice |= once & m;
+                    once |= m;
+                    self.cells[index as usize] = m;
+                }
+                broken |= once.simd_ne(ALL_DIGITS);
+                let hidden = once & !twice;
+                for index in unit {
+                    let m = self.cells[index as usize];
+                    let h = m & hidden;
+                    broken |= (h & (h - ONE)).simd_ne(NONE);
+                    let new = h.simd_ne(NONE).select(h, m);
+                    changed |= new.simd_ne(m);
+                    self.cells[index as usize] = new;
+                }
+            }
+            for m in &self.cells {
+                broken |= m.simd_eq(NONE);
+            }
+            //Broken lanes may keep changing, they are thrown away anyway
+            if !(changed & !broken).any() {
+                return broken;
+            }
+        }
+    }
+
+    fn lane(&self, lane: usize) -> (Sudoku, [CandidateSet; 81]) {
+        let sudoku = Sudoku::from(core::array::from_fn::<u8, 81, _>(|index| {
+            let m = self.cells[index][lane];
+            if m.is_power_of_two() {
+                m.trailing_zeros() as u8 + 1
+            } else {
+                0
+            }
+        }));
+        (
+            sudoku,
+            core::array::from_fn(|index| CandidateSet(self.cells[index][lane])),
+        )
+    }
+}
+
+/// Solves `puzzles` `LANES` at a time. Returns the first solution found for each puzzle, `None`
+/// when it has none.
+pub fn solve_simd(puzzles: &[Sudoku]) -> Vec<Option<Sudoku>> {
+    let mut solutions = Vec::with_capacity(puzzles.len());
+    for chunk in puzzles.chunks(LANES) {
+        let mut grid = LaneGrid::load(chunk);
+        let broken = grid.propagate();
+        for lane in 0..chunk.len() {
+            if broken.test(lane) {
+                solutions.push(None);
+                continue;
+            }
+            let (mut sudoku, allowed_candidates) = grid.lane(lane);
+            if (0..81).all(|index| !sudoku.is_missing(index)) {
+                solutions.push(Some(sudoku));
+                continue;
+            }
+            //Lanes diverge from here on, finish this one on its own
+            let solution = core::cell::Cell::new(None);
+            solve_single_thread_dynamic(
+                &mut sudoku,
+                &allowed_candidates,
+                |s| {
+                    solution.set(Some(s.clone()));
+                    true
+                },
+                || false,
+            );
+            solutions.push(solution.take());
+        }
+    }
+    solutions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ALL_CANDIDATES, DEFAULT_CHECK_INTERVAL, DynamicBacktracking, MaybeValid, SolverBackend,
+    };
+
+    #[test]
+    fn test_matches_scalar_search() {
+        let mut puzzles: Vec<Sudoku> = [
+            "000720030007006820106008709003091000580407200000000006840650010600143900005000402",
+            "900724030030050784100083000093400006001208009000900370016000040304860020200040000",
+            "000080930379500040000073500004300070810090000700406001107609854040700000926000003",
+            //Needs search after propagation
+            "001000000005000000900000200000040000000057000000310402040500630600400805009000000",
+            //No digit left for the last cell of the first row
+            "123456780000000009000000000000000000000000000000000000000000000000000000000000000",
+            //Two 9s in the first row
+            "990724030030050784100083000093400006001208009000900370016000040304860020200040000",
+        ]
+        .into_iter()
+        .map(Sudoku::from)
+        .collect();
+        //More puzzles than lanes, so the last chunk is only partly filled
+        puzzles = puzzles.iter().cycle().take(LANES + 3).cloned().collect();
+
+        let solutions = solve_simd(&puzzles);
+        assert_eq!(solutions.len(), puzzles.len());
+        for (k, (puzzle, solution)) in puzzles.iter().zip(&solutions).enumerate() {
+            match k % 6 {
+                4 | 5 => assert!(solution.is_none()),
+                _ => {
+                    let solution = solution.as_ref().unwrap();
+                    assert!(solution.is_valid());
+                    assert!((0..81).all(|index| puzzle.is_missing(index)
+                        || puzzle.get(index) == solution.get(index)));
+                    if k % 6 != 3 {
+                        //Unique puzzles, the scalar search must agree
+                        let expected = core::cell::Cell::new(None);
+                        DynamicBacktracking.solve(
+                            &mut puzzle.clone(),
+                            &ALL_CANDIDATES,
+                            &|s| {
+                                expected.set(Some(s.clone()));
+                                true
+                            },
+                            &|| false,
+                            DEFAULT_CHECK_INTERVAL,
+                        );
+                        assert!(expected.take().as_ref() == Some(solution));
+                    }
+                }
+            }
+        }
+    }
+}